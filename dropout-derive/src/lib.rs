@@ -0,0 +1,153 @@
+//! Derive macro for [dropout](https://docs.rs/dropout).
+//!
+//! Use it through the `derive` feature of dropout, see [`DeferDrop`].
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Index, Member};
+
+/// Implement `Drop` to send the fields of a struct to a dropper.
+///
+/// When the struct is dropped, all its fields are taken (with `std::mem::take`, so they must
+/// implement `Default`) and sent to the process-global dropper (see `dropout::dropout`).
+///
+/// To send only some heavy fields, mark them with `#[dropout]`: the other fields are dropped
+/// normally (and don't need to implement `Default`).
+///
+/// Another dropper can be set with `#[defer_drop(dropper = expr)]` on the struct, `expr` being
+/// any [`DropOutlet`](https://docs.rs/dropout/latest/dropout/trait.DropOutlet.html) of the
+/// field types (evaluated each time the struct is dropped).
+///
+/// # Example
+///
+/// ```
+/// use dropout::DeferDrop;
+/// use std::collections::HashMap;
+///
+/// #[derive(DeferDrop)]
+/// struct Index {
+///     entries: HashMap<String, Vec<u64>>,
+///     names: Vec<String>,
+/// }
+///
+/// let index = Index {
+///     entries: HashMap::new(),
+///     names: vec![String::from("a")],
+/// };
+/// drop(index); // `entries` and `names` are dropped in the global dropper thread.
+/// ```
+///
+/// With a configured dropper:
+///
+/// ```
+/// use dropout::{AnyDropper, DeferDrop};
+/// use std::sync::OnceLock;
+///
+/// fn heavy_dropper() -> &'static AnyDropper {
+///     static DROPPER: OnceLock<AnyDropper> = OnceLock::new();
+///     DROPPER.get_or_init(AnyDropper::new)
+/// }
+///
+/// #[derive(DeferDrop)]
+/// #[defer_drop(dropper = heavy_dropper())]
+/// struct Buffers(Vec<Vec<u8>>);
+///
+/// drop(Buffers(vec![vec![0; 1024]; 16]));
+/// heavy_dropper().flush();
+/// ```
+///
+/// Sending only some fields:
+///
+/// ```
+/// use dropout::DeferDrop;
+/// use std::collections::HashMap;
+/// use std::fs::File;
+///
+/// #[derive(DeferDrop)]
+/// struct Cache {
+///     #[dropout]
+///     entries: HashMap<u64, Vec<u8>>,
+///     // Dropped in the current thread, `File` doesn't implement `Default`.
+///     log: Option<File>,
+///     hits: u64,
+/// }
+///
+/// drop(Cache { entries: HashMap::new(), log: None, hits: 0 });
+/// ```
+#[proc_macro_derive(DeferDrop, attributes(defer_drop, dropout))]
+pub fn derive_defer_drop(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "DeferDrop can only be derived for structs",
+            ))
+        }
+    };
+    let dropper = parse_dropper(&input)?;
+    let members: Vec<(Member, bool)> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(index)),
+            };
+            let marked = field
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("dropout"));
+            (member, marked)
+        })
+        .collect();
+    // Without marked fields, all the fields are sent.
+    let any_marked = members.iter().any(|(_, marked)| *marked);
+    let members = members
+        .into_iter()
+        .filter(|(_, marked)| *marked || !any_marked)
+        .map(|(member, _)| member);
+    let sends = members.map(|member| {
+        let value = quote! { ::core::mem::take(&mut self.#member) };
+        match &dropper {
+            Some(dropper) => quote! { ::dropout::DropOutlet::dropout(&#dropper, #value); },
+            None => quote! { ::dropout::dropout(#value); },
+        }
+    });
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::ops::Drop for #name #type_generics #where_clause {
+            fn drop(&mut self) {
+                #(#sends)*
+            }
+        }
+    })
+}
+
+/// Parse the `#[defer_drop(dropper = expr)]` attribute of the struct.
+fn parse_dropper(input: &DeriveInput) -> syn::Result<Option<Expr>> {
+    let mut dropper = None;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("defer_drop") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("dropper") {
+                dropper = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported defer_drop attribute, expected `dropper = ...`"))
+            }
+        })?;
+    }
+    Ok(dropper)
+}
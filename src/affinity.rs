@@ -0,0 +1,110 @@
+/// The cores the dropper threads run on.
+#[derive(Clone, Debug)]
+pub(crate) enum Affinity {
+    Cores(Vec<usize>),
+    /// The cores of a NUMA node.
+    NumaNode(usize),
+}
+
+impl Affinity {
+    /// Pin the current thread to the cores, leaving it unchanged on failure.
+    pub(crate) fn apply(&self) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        linux::apply(self);
+        #[cfg(windows)]
+        windows::apply(self);
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux {
+    use super::Affinity;
+    use std::fs;
+    use std::os::raw::{c_int, c_ulong};
+
+    const CPU_SETSIZE: usize = 1024;
+    const BITS: usize = c_ulong::BITS as usize;
+
+    extern "C" {
+        fn sched_setaffinity(pid: c_int, size: usize, mask: *const c_ulong) -> c_int;
+    }
+
+    pub(super) fn apply(affinity: &Affinity) {
+        let cores = match affinity {
+            Affinity::Cores(cores) => cores.clone(),
+            Affinity::NumaNode(node) => {
+                let path = format!("/sys/devices/system/node/node{node}/cpulist");
+                match fs::read_to_string(path) {
+                    Ok(list) => parse_list(&list),
+                    Err(_) => return,
+                }
+            }
+        };
+        let mut set = [0 as c_ulong; CPU_SETSIZE / BITS];
+        for core in cores.into_iter().filter(|core| *core < CPU_SETSIZE) {
+            set[core / BITS] |= 1 << (core % BITS);
+        }
+        if set.iter().all(|word| *word == 0) {
+            return;
+        }
+        // SAFETY: `set` is a valid `cpu_set_t` of `size_of_val(&set)` bytes, only read during the
+        // call. The id 0 designates the calling thread.
+        unsafe {
+            sched_setaffinity(0, std::mem::size_of_val(&set), set.as_ptr());
+        }
+    }
+
+    /// Parse a list of cores such as `0-3,8,10-11`, ignoring the invalid parts.
+    fn parse_list(list: &str) -> Vec<usize> {
+        let mut cores = Vec::new();
+        for range in list.trim().split(',') {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            if let (Ok(first), Ok(last)) = (first.parse::<usize>(), last.parse::<usize>()) {
+                cores.extend(first..=last);
+            }
+        }
+        cores
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::Affinity;
+    use std::ffi::c_void;
+    use std::os::raw::{c_int, c_uchar, c_ulonglong};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadAffinityMask(thread: *mut c_void, mask: usize) -> usize;
+        fn GetNumaNodeProcessorMask(node: c_uchar, mask: *mut c_ulonglong) -> c_int;
+    }
+
+    // Only the cores of the processor group of the thread (the first 64 ones) can be used.
+    pub(super) fn apply(affinity: &Affinity) {
+        let mask = match affinity {
+            Affinity::Cores(cores) => cores
+                .iter()
+                .filter(|core| **core < usize::BITS as usize)
+                .fold(0, |mask, core| mask | 1 << core),
+            Affinity::NumaNode(node) => {
+                let Ok(node) = c_uchar::try_from(*node) else {
+                    return;
+                };
+                let mut mask = 0;
+                // SAFETY: `mask` is valid for writes during the call.
+                if unsafe { GetNumaNodeProcessorMask(node, &mut mask) } == 0 {
+                    return;
+                }
+                mask as usize
+            }
+        };
+        if mask == 0 {
+            return;
+        }
+        // SAFETY: the pseudo handle of the current thread is always valid.
+        unsafe {
+            SetThreadAffinityMask(GetCurrentThread(), mask);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::inner::{self, ErasedDropper};
+use crate::{Dropper, DropperBuilder, DropperStats, ShutdownError};
+use std::any::{self, Any};
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A dropper accepting values of any type.
+///
+/// `AnyDropper` boxes the values it receives and sends them to one dropper thread,
+/// so one handle (and one thread) can serve many types instead of a `Dropper<T>` per type.
+///
+/// `AnyDropper` is fully type erased: all the dropper machinery is instantiated once,
+/// whatever the number of types sent to it.
+///
+/// A typed [`Dropper`] can be converted to an `AnyDropper` using the same dropper thread with
+/// [`Dropper::erase`]. This is also the way to create an `AnyDropper` with a
+/// [`DropperBuilder`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::AnyDropper;
+/// # use std::collections::HashMap;
+/// let dropper = AnyDropper::new();
+/// dropper.dropout(vec![0u8; 1024]);
+/// dropper.dropout(HashMap::<u32, String>::new());
+/// dropper.dropout(String::from("Hello"));
+/// ```
+pub struct AnyDropper(Arc<dyn ErasedDropper>);
+
+impl AnyDropper {
+    /// Create a new AnyDropper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dropper thread cannot be created. See [`try_new`](Self::try_new).
+    #[inline]
+    pub fn new() -> Self {
+        Self::try_new().expect("Should succeed to create thread")
+    }
+
+    /// Create a new AnyDropper, returning an error if the dropper thread cannot be created.
+    #[inline]
+    pub fn try_new() -> io::Result<Self> {
+        inner::Dropper::<Box<dyn Any + Send>>::new(DropperBuilder::new()).map(|d| Self(Arc::new(d)))
+    }
+
+    /// Send a value to be dropped in the dropper thread.
+    ///
+    /// See [`Dropper::dropout`].
+    #[inline]
+    pub fn dropout<U: Send + 'static>(&self, to_drop: U) {
+        self.0
+            .dropout_erased(Box::new(to_drop), any::type_name::<U>())
+    }
+
+    /// Send a closure to be run in the dropper thread. See [`Dropper::defer`].
+    #[inline]
+    pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+        let (job, type_name) = inner::job(f);
+        self.0.dropout_erased(job, type_name)
+    }
+
+    /// Send a future to be run to completion in the dropper thread.
+    /// See [`Dropper::defer_async`].
+    #[inline]
+    pub fn defer_async(&self, cleanup: impl Future<Output = ()> + Send + 'static) {
+        let (cleanup, type_name) = inner::cleanup(cleanup);
+        self.0.defer_cleanup(cleanup, type_name)
+    }
+
+    /// Send an already boxed value to be dropped in the dropper thread.
+    ///
+    /// The type of the value is unknown: it is reported as `dyn Any + Send` in the
+    /// [events](crate::DropEvent) and [stats](crate::DropperStats).
+    #[inline]
+    pub fn dropout_box(&self, to_drop: Box<dyn Any + Send>) {
+        self.0
+            .dropout_erased(to_drop, any::type_name::<dyn Any + Send>())
+    }
+
+    /// Try to send a value to be dropped in the dropper thread, giving it back on failure.
+    ///
+    /// See [`Dropper::try_dropout`].
+    #[inline]
+    pub fn try_dropout<U: Send + 'static>(&self, to_drop: U) -> Result<(), U> {
+        self.0
+            .try_dropout_erased(Box::new(to_drop), any::type_name::<U>())
+            .map_err(|to_drop| *to_drop.downcast().unwrap())
+    }
+
+    /// Wait for all values sent before this call to be dropped. See [`Dropper::flush`].
+    #[inline]
+    pub fn flush(&self) {
+        self.0.flush()
+    }
+
+    /// Wait at most `timeout` for all values sent before this call to be dropped.
+    /// See [`Dropper::flush_timeout`].
+    #[inline]
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.0.flush_timeout(timeout)
+    }
+
+    /// Number of values sent and not yet dropped. See [`Dropper::pending`].
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.0.pending()
+    }
+
+    /// A snapshot of the activity of the dropper thread. See [`Dropper::stats`].
+    #[inline]
+    pub fn stats(&self) -> DropperStats {
+        self.0.stats()
+    }
+
+    /// Returns `true` if the dropper thread is still running. See [`Dropper::is_healthy`].
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.0.is_healthy()
+    }
+
+    /// Stop the dropper thread and wait for it to finish. See [`Dropper::shutdown`].
+    #[inline]
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.0.shutdown()
+    }
+}
+
+impl<T: Send + 'static> From<Dropper<T>> for AnyDropper {
+    /// See [`Dropper::erase`].
+    fn from(dropper: Dropper<T>) -> Self {
+        Self(dropper.0)
+    }
+}
+
+impl Default for AnyDropper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for AnyDropper {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{Closed, Dropped, Dropper, DropperBuilder, TokioSpawner};
+use std::io;
+use std::panic;
+use tokio::runtime::Handle;
+#[cfg(feature = "tokio-util")]
+use {crate::ShutdownOutcome, std::time::Duration, tokio_util::sync::CancellationToken};
+
+/// A dropper whose consumer runs on a tokio runtime instead of a dedicated thread.
+///
+/// Async applications already have a pool of threads, `AsyncDropper` uses it: the values are
+/// dropped by a task spawned on the current runtime. As a task must not block its worker
+/// thread for long, values slow to drop should be sent to a dropper created with
+/// [`new_blocking`](Self::new_blocking), whose consumer runs on the blocking threads of the
+/// runtime.
+///
+/// Sending a value is asynchronous: with a bounded queue (see [`DropperBuilder::capacity`]),
+/// [`dropout`](Self::dropout) yields to the runtime until there is room in the queue, where
+/// blocking methods would block the worker thread, maybe the one the consumer runs on.
+///
+/// Unlike a [`Dropper`], dropping the last handle doesn't wait for the values to be dropped (it
+/// would block the current thread, maybe the one the consumer runs on): the consumer drops the
+/// remaining values and completes on its own. If the runtime shuts down first, the values still
+/// in the queue are dropped with the runtime.
+///
+/// Available with the `tokio` feature.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::AsyncDropper;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let dropper = AsyncDropper::new();
+/// dropper.dropout(vec![0u8; 1024]).await;
+/// dropper.dropout(vec![1u8; 1024]).await;
+/// # }
+/// ```
+pub struct AsyncDropper<T: Send + 'static>(pub(crate) Dropper<T>);
+
+impl<T: Send + 'static> AsyncDropper<T> {
+    /// Create a new AsyncDropper, its consumer being a task of the current runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime. See [`try_new`](Self::try_new).
+    #[inline]
+    pub fn new() -> Self {
+        Self::try_new().expect("Should be called from a tokio runtime")
+    }
+
+    /// Create a new AsyncDropper, returning an error if called outside of a tokio runtime.
+    #[inline]
+    pub fn try_new() -> io::Result<Self> {
+        Self::with_builder(DropperBuilder::new())
+    }
+
+    /// Create a new AsyncDropper, its consumer being a task of the runtime of `handle`.
+    ///
+    /// This can be called outside of the runtime, the consumer runs on its worker threads and
+    /// stops with it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::AsyncDropper;
+    /// let runtime = tokio::runtime::Builder::new_multi_thread()
+    ///     .worker_threads(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let dropper = AsyncDropper::with_handle(runtime.handle().clone());
+    /// runtime.block_on(async {
+    ///     dropper.dropout(vec![0u8; 1024]).await;
+    ///     dropper.flush().await;
+    /// });
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn with_handle(handle: Handle) -> Self {
+        Self::with_builder_on(DropperBuilder::new(), handle)
+            .expect("Spawning a task should not fail")
+    }
+
+    /// Create a new AsyncDropper, its consumer running on the blocking threads of the current
+    /// runtime (see [`spawn_blocking`](tokio::task::spawn_blocking)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    #[inline]
+    pub fn new_blocking() -> Self {
+        Self::with_builder_blocking(DropperBuilder::new())
+            .expect("Should be called from a tokio runtime")
+    }
+
+    /// Create a new AsyncDropper configured by `builder`, its consumer being a task of the current
+    /// runtime.
+    ///
+    /// The thread name and stack size of `builder` are not used, and there is only one consumer
+    /// task, whatever the number of [`workers`](DropperBuilder::workers).
+    pub fn with_builder(builder: DropperBuilder) -> io::Result<Self> {
+        let handle = Handle::try_current().map_err(io::Error::other)?;
+        Self::with_builder_on(builder, handle)
+    }
+
+    /// Create a new AsyncDropper configured by `builder`, its consumer being a task of the
+    /// runtime of `handle`. See [`with_builder`](Self::with_builder).
+    pub fn with_builder_on(builder: DropperBuilder, handle: Handle) -> io::Result<Self> {
+        builder
+            .workers(1)
+            .build_with(TokioSpawner::new(handle))
+            .map(Self)
+    }
+
+    /// Create a new AsyncDropper configured by `builder`, its consumers running on the blocking
+    /// threads of the current runtime.
+    ///
+    /// The thread name and stack size of `builder` are not used (blocking threads are configured
+    /// with the runtime).
+    pub fn with_builder_blocking(builder: DropperBuilder) -> io::Result<Self> {
+        builder
+            .build_with(TokioSpawner::current()?.blocking())
+            .map(Self)
+    }
+
+    /// Send a value to be dropped by the consumer, waiting for room in the queue without blocking
+    /// the current thread.
+    ///
+    /// With an unbounded queue, the future completes immediately. With a bounded one (see
+    /// [`DropperBuilder::capacity`]), it yields to the runtime until there is room in the queue:
+    /// this gives backpressure to the producers. Use [`try_dropout`](Self::try_dropout) or
+    /// [`as_dropper`](Self::as_dropper) to send values from synchronous code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{AsyncDropper, DropperBuilder};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let dropper = AsyncDropper::with_builder(DropperBuilder::new().capacity(4)).unwrap();
+    /// for i in 0..100 {
+    ///     // Yields to the consumer task when the queue is full.
+    ///     dropper.dropout(vec![i; 1024]).await;
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    pub async fn dropout(&self, to_drop: T) {
+        self.0.dropout_async(to_drop).await
+    }
+
+    /// Send a value to be dropped by the consumer, returning a future completing once it has been
+    /// dropped. See [`Dropper::dropout_notified`].
+    #[inline]
+    pub fn dropout_notified(&self, to_drop: T) -> Dropped {
+        self.0.dropout_notified(to_drop)
+    }
+
+    /// Try to send a value to be dropped by the consumer, giving it back on failure.
+    /// See [`Dropper::try_dropout`].
+    #[inline]
+    pub fn try_dropout(&self, to_drop: T) -> Result<(), T> {
+        self.0.try_dropout(to_drop)
+    }
+
+    /// Wait for all values sent before this call to be dropped, without blocking the current
+    /// thread. See [`Dropper::flush_async`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::AsyncDropper;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let dropper = AsyncDropper::new();
+    /// dropper.dropout(vec![0u8; 1024]).await;
+    /// dropper.flush().await;
+    /// assert_eq!(dropper.pending(), 0);
+    /// # }
+    /// ```
+    #[inline]
+    pub async fn flush(&self) {
+        self.0.flush_async().await
+    }
+
+    /// Stop the consumer once `token` is cancelled, waiting at most `timeout` for it to finish.
+    /// See [`Dropper::shutdown_on`].
+    ///
+    /// Available with the `tokio-util` feature.
+    #[cfg(feature = "tokio-util")]
+    #[inline]
+    pub async fn shutdown_on(self, token: CancellationToken, timeout: Duration) -> ShutdownOutcome {
+        self.0.shutdown_on(token, timeout).await
+    }
+
+    /// Wait until the queue has at least `slots` free slots. See [`Dropper::wait_for_capacity`].
+    #[inline]
+    pub async fn wait_for_capacity(&self, slots: usize) {
+        self.0.wait_for_capacity(slots).await
+    }
+
+    /// Number of values sent and not yet dropped. See [`Dropper::pending`].
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.0.pending()
+    }
+
+    /// Get a future completing once the consumer has stopped. See [`Dropper::closed`].
+    #[inline]
+    pub fn closed(&self) -> Closed {
+        self.0.closed()
+    }
+
+    /// Returns `true` if the consumer is still running. See [`Dropper::is_healthy`].
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.0.is_healthy()
+    }
+
+    /// The underlying [`Dropper`], giving access to its synchronous API.
+    ///
+    /// Blocking methods (like [`Dropper::flush`]) block the current thread: they must not be
+    /// called from a task of a current thread runtime running the consumer.
+    #[inline]
+    pub fn as_dropper(&self) -> &Dropper<T> {
+        &self.0
+    }
+}
+
+impl<T: Send + 'static> Default for AsyncDropper<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> Clone for AsyncDropper<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Drop `value` on the blocking threads of the current tokio runtime, completing once dropped.
+///
+/// This is meant for occasional heavy drops in async code, without creating an
+/// [`AsyncDropper`]. If dropping `value` panics, the panic is resumed when awaiting.
+///
+/// Available with the `tokio` feature.
+///
+/// # Panics
+///
+/// Panics if called outside of a tokio runtime.
+///
+/// # Example
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let heavy: HashMap<usize, Vec<usize>> = (0..1000).map(|v| (v, vec![v])).collect();
+/// dropout::blocking(heavy).await;
+/// # }
+/// ```
+pub async fn blocking<T: Send + 'static>(value: T) {
+    if let Err(error) = tokio::task::spawn_blocking(move || drop(value)).await {
+        if let Ok(payload) = error.try_into_panic() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
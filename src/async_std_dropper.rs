@@ -0,0 +1,155 @@
+use crate::{AsyncStdSpawner, Closed, Dropped, Dropper, DropperBuilder};
+use std::io;
+
+/// A dropper whose consumer runs on the async-std executor instead of a dedicated thread.
+///
+/// This is the async-std counterpart of `AsyncDropper`: the values are dropped by a task spawned
+/// on the global executor. As a task must not block its worker thread for long, values slow to
+/// drop should be sent to a dropper created with [`new_blocking`](Self::new_blocking), whose
+/// consumer runs on the blocking threads of async-std.
+///
+/// Unlike a [`Dropper`], dropping the last handle doesn't wait for the values to be dropped: the
+/// consumer drops the remaining values and completes on its own.
+///
+/// Available with the `async-std` feature.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::AsyncStdDropper;
+/// # async_std::task::block_on(async {
+/// let dropper = AsyncStdDropper::new();
+/// dropper.dropout(vec![0u8; 1024]).await;
+/// dropper.dropout(vec![1u8; 1024]).await;
+/// # });
+/// ```
+pub struct AsyncStdDropper<T: Send + 'static>(pub(crate) Dropper<T>);
+
+impl<T: Send + 'static> AsyncStdDropper<T> {
+    /// Create a new AsyncStdDropper, its consumer being a task of the async-std executor.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_builder(DropperBuilder::new()).expect("Spawning a task should not fail")
+    }
+
+    /// Create a new AsyncStdDropper, its consumer running on the blocking threads of async-std
+    /// (see [`spawn_blocking`](async_std::task::spawn_blocking)).
+    #[inline]
+    pub fn new_blocking() -> Self {
+        Self::with_builder_blocking(DropperBuilder::new())
+            .expect("Spawning a blocking task should not fail")
+    }
+
+    /// Create a new AsyncStdDropper configured by `builder`, its consumer being a task of the
+    /// async-std executor.
+    ///
+    /// The thread name and stack size of `builder` are not used, and there is only one consumer
+    /// task, whatever the number of [`workers`](DropperBuilder::workers).
+    pub fn with_builder(builder: DropperBuilder) -> io::Result<Self> {
+        builder
+            .workers(1)
+            .build_with(AsyncStdSpawner::new())
+            .map(Self)
+    }
+
+    /// Create a new AsyncStdDropper configured by `builder`, its consumers running on the blocking
+    /// threads of async-std.
+    ///
+    /// The thread name and stack size of `builder` are not used (blocking threads are managed by
+    /// async-std).
+    pub fn with_builder_blocking(builder: DropperBuilder) -> io::Result<Self> {
+        builder
+            .build_with(AsyncStdSpawner::new().blocking())
+            .map(Self)
+    }
+
+    /// Send a value to be dropped by the consumer, waiting for room in the queue without blocking
+    /// the current thread.
+    ///
+    /// With an unbounded queue, the future completes immediately. With a bounded one (see
+    /// [`DropperBuilder::capacity`]), it yields to the runtime until there is room in the queue:
+    /// this gives backpressure to the producers. Use [`try_dropout`](Self::try_dropout) or
+    /// [`as_dropper`](Self::as_dropper) to send values from synchronous code.
+    #[inline]
+    pub async fn dropout(&self, to_drop: T) {
+        self.0.dropout_async(to_drop).await
+    }
+
+    /// Send a value to be dropped by the consumer, returning a future completing once it has been
+    /// dropped. See [`Dropper::dropout_notified`].
+    #[inline]
+    pub fn dropout_notified(&self, to_drop: T) -> Dropped {
+        self.0.dropout_notified(to_drop)
+    }
+
+    /// Try to send a value to be dropped by the consumer, giving it back on failure.
+    /// See [`Dropper::try_dropout`].
+    #[inline]
+    pub fn try_dropout(&self, to_drop: T) -> Result<(), T> {
+        self.0.try_dropout(to_drop)
+    }
+
+    /// Wait for all values sent before this call to be dropped, without blocking the current
+    /// thread. See [`Dropper::flush_async`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::AsyncStdDropper;
+    /// # async_std::task::block_on(async {
+    /// let dropper = AsyncStdDropper::new();
+    /// dropper.dropout(vec![0u8; 1024]).await;
+    /// dropper.flush().await;
+    /// assert_eq!(dropper.pending(), 0);
+    /// # });
+    /// ```
+    #[inline]
+    pub async fn flush(&self) {
+        self.0.flush_async().await
+    }
+
+    /// Wait until the queue has at least `slots` free slots. See [`Dropper::wait_for_capacity`].
+    #[inline]
+    pub async fn wait_for_capacity(&self, slots: usize) {
+        self.0.wait_for_capacity(slots).await
+    }
+
+    /// Number of values sent and not yet dropped. See [`Dropper::pending`].
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.0.pending()
+    }
+
+    /// Get a future completing once the consumer has stopped. See [`Dropper::closed`].
+    #[inline]
+    pub fn closed(&self) -> Closed {
+        self.0.closed()
+    }
+
+    /// Returns `true` if the consumer is still running. See [`Dropper::is_healthy`].
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.0.is_healthy()
+    }
+
+    /// The underlying [`Dropper`], giving access to its synchronous API.
+    ///
+    /// Blocking methods (like [`Dropper::flush`]) block the current thread: they should not be
+    /// called from a task.
+    #[inline]
+    pub fn as_dropper(&self) -> &Dropper<T> {
+        &self.0
+    }
+}
+
+impl<T: Send + 'static> Default for AsyncStdDropper<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> Clone for AsyncStdDropper<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::Dropper;
+use std::mem;
+
+/// A guard collecting values to send them to a [`Dropper`] in one message.
+///
+/// The values are sent when the guard is dropped or [`commit`](Self::commit)ted, with
+/// [`Dropper::dropout_many`].
+///
+/// Created with [`Dropper::batch`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// # use std::collections::HashMap;
+/// let dropper = Dropper::new();
+/// let mut cache: HashMap<u32, Vec<u8>> = (0..100).map(|i| (i, vec![0; 1024])).collect();
+/// let mut batch = dropper.batch();
+/// cache.retain(|key, value| {
+///     if key % 2 == 0 {
+///         batch.push(std::mem::take(value));
+///         false
+///     } else {
+///         true
+///     }
+/// });
+/// assert_eq!(batch.len(), 50);
+/// batch.commit();
+/// ```
+pub struct Batch<'a, T: Send + 'static> {
+    dropper: &'a Dropper<T>,
+    values: Vec<T>,
+}
+
+impl<'a, T: Send + 'static> Batch<'a, T> {
+    #[inline]
+    pub(crate) fn new(dropper: &'a Dropper<T>) -> Self {
+        Self {
+            dropper,
+            values: Vec::new(),
+        }
+    }
+
+    /// Add a value to the batch.
+    #[inline]
+    pub fn push(&mut self, to_drop: T) {
+        self.values.push(to_drop)
+    }
+
+    /// Number of values in the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the batch has no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Send the values of the batch now.
+    #[inline]
+    pub fn commit(self) {
+        // Sent when dropped.
+    }
+}
+
+impl<T: Send + 'static> Extend<T> for Batch<'_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.values.extend(iter)
+    }
+}
+
+impl<T: Send + 'static> Drop for Batch<'_, T> {
+    fn drop(&mut self) {
+        self.dropper.dropout_many(mem::take(&mut self.values));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{delay, Dropper};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A producer handle buffering values to send them to a [`Dropper`] in batches.
+///
+/// Values given to [`dropout`](Self::dropout) are kept in a local buffer, sent in one message
+/// (see [`Dropper::dropout_many`]) when a threshold is reached:
+/// - the number of buffered values reaches [`max_count`](Self::max_count) (64 by default),
+/// - the total size of the buffered values reaches [`max_size`](Self::max_size), if set,
+/// - the oldest buffered value has waited for [`max_delay`](Self::max_delay), if set.
+///
+/// This reduces the per value overhead for high-frequency small drops. The delay is also checked
+/// by the timer thread (see [`Dropper::dropout_after`]), so the values are sent even if no value
+/// is buffered after them: buffering never delays a drop for more than `max_delay`. The buffer is
+/// also sent by [`flush`](Self::flush) and when the handle is dropped.
+///
+/// Each producer thread uses its own `BufferedDropper`, created with [`Dropper::buffered`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// # use std::time::Duration;
+/// let dropper = Dropper::new();
+/// let mut buffered = dropper
+///     .buffered()
+///     .max_count(100)
+///     .max_size(64 * 1024, |v: &Vec<u8>| v.capacity())
+///     .max_delay(Duration::from_millis(10));
+/// for _ in 0..1000 {
+///     buffered.dropout(vec![0; 128]);
+/// }
+/// buffered.flush();
+/// ```
+pub struct BufferedDropper<T: Send + 'static> {
+    dropper: Dropper<T>,
+    /// Shared with the timer thread, sending the buffer once `max_delay` has elapsed.
+    buffer: Arc<Mutex<Buffer<T>>>,
+    max_count: usize,
+    max_size: usize,
+    size_of: fn(&T) -> usize,
+    max_delay: Option<Duration>,
+}
+
+struct Buffer<T> {
+    values: Vec<T>,
+    size: usize,
+    /// When the oldest value was buffered.
+    oldest: Option<Instant>,
+}
+
+impl<T> Buffer<T> {
+    /// Take the buffered values.
+    ///
+    /// The next buffer grows as values are buffered: `max_count` may be unbounded.
+    fn take(&mut self) -> Vec<T> {
+        self.size = 0;
+        self.oldest = None;
+        mem::take(&mut self.values)
+    }
+}
+
+impl<T: Send + 'static> BufferedDropper<T> {
+    #[inline]
+    pub(crate) fn new(dropper: Dropper<T>) -> Self {
+        Self {
+            dropper,
+            buffer: Arc::new(Mutex::new(Buffer {
+                values: Vec::new(),
+                size: 0,
+                oldest: None,
+            })),
+            max_count: 64,
+            max_size: usize::MAX,
+            size_of: |_| 0,
+            max_delay: None,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buffer<T>> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send the buffer when it holds `max_count` values.
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.max_count = max_count;
+        self
+    }
+
+    /// Send the buffer when the total size of its values, as computed by `size`,
+    /// reaches `max_size`.
+    pub fn max_size(mut self, max_size: usize, size: fn(&T) -> usize) -> Self {
+        self.max_size = max_size;
+        self.size_of = size;
+        self
+    }
+
+    /// Send the buffer once the oldest value has waited for `max_delay`.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// Buffer a value, sending the buffer if a threshold is reached.
+    pub fn dropout(&mut self, to_drop: T) {
+        let size = (self.size_of)(&to_drop);
+        let mut buffer = self.lock();
+        buffer.size = buffer.size.saturating_add(size);
+        let first = buffer.oldest.is_none();
+        let oldest = *buffer.oldest.get_or_insert_with(Instant::now);
+        buffer.values.push(to_drop);
+        let full = buffer.values.len() >= self.max_count
+            || buffer.size >= self.max_size
+            || matches!(self.max_delay, Some(max_delay) if oldest.elapsed() >= max_delay);
+        if full {
+            let values = buffer.take();
+            drop(buffer);
+            self.dropper.dropout_many(values);
+        } else if let (true, Some(max_delay)) = (first, self.max_delay) {
+            drop(buffer);
+            self.schedule(oldest, max_delay);
+        }
+    }
+
+    /// Send the buffer from the timer thread once the value buffered at `oldest` has waited for
+    /// `max_delay`, if it is still buffered.
+    fn schedule(&self, oldest: Instant, max_delay: Duration) {
+        let buffer = Arc::downgrade(&self.buffer);
+        let dropper = self.dropper.0.weak_sender();
+        delay::schedule(oldest + max_delay, move || {
+            let Some(buffer) = buffer.upgrade() else {
+                return;
+            };
+            let mut buffer = buffer.lock().unwrap_or_else(PoisonError::into_inner);
+            if buffer.oldest == Some(oldest) {
+                let values = buffer.take();
+                drop(buffer);
+                dropper.dropout_many(values);
+            }
+        });
+    }
+
+    /// Number of values in the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().values.len()
+    }
+
+    /// Returns `true` if the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().values.is_empty()
+    }
+
+    /// Send the buffered values to the dropper now.
+    ///
+    /// Unlike [`Dropper::flush`], this doesn't wait for the values to be dropped.
+    pub fn flush(&mut self) {
+        let values = self.lock().take();
+        self.dropper.dropout_many(values);
+    }
+}
+
+impl<T: Send + 'static> Drop for BufferedDropper<T> {
+    fn drop(&mut self) {
+        let values = mem::take(&mut self.lock().values);
+        self.dropper.dropout_many(values);
+    }
+}
@@ -0,0 +1,658 @@
+use crate::affinity::Affinity;
+use crate::rate::IdleProbe;
+use crate::watchdog::Watchdog;
+use crate::{
+    inner, DropOrder, Dropper, OverflowPolicy, PanicPolicy, Spawner, StuckDrop, ThreadPriority,
+    Throttle, Watermarks,
+};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builder to configure a [`Dropper`] before creating it.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{DropperBuilder, OverflowPolicy, Dropper};
+/// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+///     .name("cache-dropper")
+///     .capacity(1024)
+///     .overflow_policy(OverflowPolicy::Block)
+///     .build()
+///     .unwrap();
+/// dropper.dropout(vec![0; 1024]);
+/// ```
+#[derive(Debug)]
+pub struct DropperBuilder {
+    pub(crate) name: String,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) priority: Option<ThreadPriority>,
+    pub(crate) affinity: Option<Affinity>,
+    pub(crate) capacity: Option<usize>,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) watermarks: Option<Watermarks>,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) workers: usize,
+    pub(crate) task_budget: usize,
+    pub(crate) yield_every: Option<usize>,
+    pub(crate) order: DropOrder,
+    pub(crate) max_drops_per_second: Option<u64>,
+    pub(crate) max_bytes_per_second: Option<u64>,
+    pub(crate) throttle: Option<Throttle>,
+    pub(crate) idle_probe: Option<IdleProbe>,
+    pub(crate) max_deferral: Option<Duration>,
+    pub(crate) watchdog: Option<Watchdog>,
+    pub(crate) latency_buckets: Option<Vec<Duration>>,
+    pub(crate) track_slowest: Option<usize>,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: bool,
+    #[cfg(feature = "tokio")]
+    pub(crate) tokio_channel: bool,
+}
+
+impl DropperBuilder {
+    /// Create a builder with the default configuration
+    /// (unbounded queue, thread named "Dropout").
+    pub fn new() -> Self {
+        Self {
+            name: "Dropout".into(),
+            stack_size: None,
+            priority: None,
+            affinity: None,
+            capacity: None,
+            overflow_policy: OverflowPolicy::default(),
+            watermarks: None,
+            panic_policy: PanicPolicy::default(),
+            workers: 1,
+            task_budget: 128,
+            yield_every: None,
+            order: DropOrder::default(),
+            max_drops_per_second: None,
+            max_bytes_per_second: None,
+            throttle: None,
+            idle_probe: None,
+            max_deferral: None,
+            watchdog: None,
+            latency_buckets: None,
+            track_slowest: None,
+            #[cfg(feature = "metrics")]
+            metrics: false,
+            #[cfg(feature = "tokio")]
+            tokio_channel: false,
+        }
+    }
+
+    /// Set the name of the dropper thread.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the stack size of the dropper thread.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Set the scheduling priority of the dropper threads.
+    ///
+    /// Dropping values is not latency-sensitive: with a low priority, the dropper threads run
+    /// when the CPU is not needed by the other threads. See [`ThreadPriority`] for the support of
+    /// each system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the nice value of [`ThreadPriority::Nice`] is greater than 19.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder, ThreadPriority};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .thread_priority(ThreadPriority::Idle)
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush();
+    /// ```
+    pub fn thread_priority(mut self, priority: ThreadPriority) -> Self {
+        priority.check();
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Pin the dropper threads to `cores` (numbered from 0), so they don't share a core with
+    /// latency-critical threads.
+    ///
+    /// Pinning is best-effort: the cores which don't exist are ignored, and if none of them
+    /// exists the threads are not pinned. It is supported on Linux, Android and Windows (for the
+    /// first 64 cores), the threads are not pinned on the other systems.
+    /// Replaces the [`numa_node`](Self::numa_node) affinity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cores` is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// // Keep the dropper thread away from the core 0, running the latency-critical threads.
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .core_affinity([1, 2, 3])
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush();
+    /// ```
+    pub fn core_affinity(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        let cores: Vec<usize> = cores.into_iter().collect();
+        assert!(!cores.is_empty(), "cores must not be empty");
+        self.affinity = Some(Affinity::Cores(cores));
+        self
+    }
+
+    /// Pin the dropper threads to the cores of the NUMA node `node`.
+    ///
+    /// Freeing memory allocated on a NUMA node from a core of another node is slower, and
+    /// disturbs the threads of that node: pin the dropper threads to the node where the dropped
+    /// values were allocated. As with [`core_affinity`](Self::core_affinity), pinning is
+    /// best-effort, and supported on Linux, Android and Windows only.
+    /// Replaces the [`core_affinity`](Self::core_affinity) affinity.
+    pub fn numa_node(mut self, node: usize) -> Self {
+        self.affinity = Some(Affinity::NumaNode(node));
+        self
+    }
+
+    /// Bound the queue to `capacity` values. See [`Dropper::with_capacity`].
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Set what [`Dropper::dropout`] does when the queue is full.
+    ///
+    /// This is only meaningful with a bounded queue (see [`capacity`](Self::capacity)).
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set the watermarks callbacks. See [`Dropper::with_watermarks`].
+    pub fn watermarks(mut self, watermarks: Watermarks) -> Self {
+        self.watermarks = Some(watermarks);
+        self
+    }
+
+    /// Set what happens when the dropper thread panics (probably because a dropped value panicked).
+    ///
+    /// See [`PanicPolicy`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder, PanicPolicy};
+    /// struct Bomb;
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("Boom");
+    ///     }
+    /// }
+    ///
+    /// let dropper: Dropper<Option<Bomb>> = DropperBuilder::new()
+    ///     .panic_policy(PanicPolicy::Ignore)
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(Some(Bomb));
+    /// dropper.flush();
+    /// assert!(dropper.is_healthy());
+    /// ```
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// Restart the dropper thread if it panics (probably because a dropped value panicked).
+    ///
+    /// This is a shortcut for `panic_policy(PanicPolicy::Restart)` (or
+    /// `panic_policy(PanicPolicy::Propagate)` if `restart` is `false`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// struct Bomb;
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("Boom");
+    ///     }
+    /// }
+    ///
+    /// let dropper: Dropper<Option<Bomb>> = DropperBuilder::new()
+    ///     .restart_on_panic(true)
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(Some(Bomb));
+    /// dropper.flush();
+    /// assert!(dropper.is_healthy());
+    /// dropper.dropout(None);
+    /// assert!(dropper.shutdown().is_ok());
+    /// ```
+    pub fn restart_on_panic(self, restart: bool) -> Self {
+        self.panic_policy(if restart {
+            PanicPolicy::Restart
+        } else {
+            PanicPolicy::Propagate
+        })
+    }
+
+    /// Set the number of dropper threads consuming the queue. See [`Dropper::with_workers`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0.
+    pub fn workers(mut self, workers: usize) -> Self {
+        assert!(workers > 0, "A dropper needs at least one worker");
+        self.workers = workers;
+        self
+    }
+
+    /// Set the number of messages a consumer task handles before yielding to its executor
+    /// (128 by default).
+    ///
+    /// This only applies to droppers whose consumer runs as a task (see
+    /// [`build_with`](Self::build_with)), so that a burst of values doesn't starve the other
+    /// tasks running on the same thread. A message is a value, or a batch of values sent at once.
+    /// A collection sent with [`Dropper::dropout_chunked`] counts as one message per chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is 0.
+    pub fn task_budget(mut self, messages: usize) -> Self {
+        assert!(
+            messages > 0,
+            "A consumer task needs to handle at least one message"
+        );
+        self.task_budget = messages;
+        self
+    }
+
+    /// Yield the dropper thread (see [`yield_now`](std::thread::yield_now)) every time it has
+    /// handled `messages` messages.
+    ///
+    /// By default the dropper thread drops the values as long as there are some, which can delay
+    /// the other threads on a heavily loaded machine. This is the counterpart, for threads, of
+    /// [`task_budget`](Self::task_budget).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .yield_every(16)
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// ```
+    pub fn yield_every(mut self, messages: usize) -> Self {
+        assert!(
+            messages > 0,
+            "The dropper thread needs to handle at least one message"
+        );
+        self.yield_every = Some(messages);
+        self
+    }
+
+    /// Set the order in which the values are dropped. See [`DropOrder`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{DropOrder, Dropper, DropperBuilder};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .order(DropOrder::Lifo)
+    ///     .build()
+    ///     .unwrap();
+    /// for i in 0..10 {
+    ///     dropper.dropout(vec![i; 1024]);
+    /// }
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    pub fn order(mut self, order: DropOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Limit the number of values dropped per second.
+    ///
+    /// The drops are spread over time instead of happening in bursts, so the work of the
+    /// allocator in the dropper thread competes less with the other threads. Values are queued
+    /// meanwhile: with a bounded queue, a rate too low for the producers applies the
+    /// [`overflow_policy`](Self::overflow_policy). Flushes and shutdowns wait for the values to be
+    /// dropped at this rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drops` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// # use std::time::{Duration, Instant};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .max_drops_per_second(100)
+    ///     .build()
+    ///     .unwrap();
+    /// let start = Instant::now();
+    /// for _ in 0..10 {
+    ///     dropper.dropout(vec![0u8; 1024]);
+    /// }
+    /// dropper.flush();
+    /// assert!(start.elapsed() >= Duration::from_millis(90));
+    /// ```
+    pub fn max_drops_per_second(mut self, drops: u64) -> Self {
+        assert!(drops > 0, "The rate limit must allow some drops");
+        self.max_drops_per_second = Some(drops);
+        self
+    }
+
+    /// Limit the number of bytes released per second, as given by
+    /// [`Dropper::dropout_sized`] and [`Dropper::dropout_measured`].
+    ///
+    /// Values sent with the other methods count as 0 bytes. See
+    /// [`max_drops_per_second`](Self::max_drops_per_second).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is 0.
+    pub fn max_bytes_per_second(mut self, bytes: u64) -> Self {
+        assert!(bytes > 0, "The rate limit must allow some bytes");
+        self.max_bytes_per_second = Some(bytes);
+        self
+    }
+
+    /// Pause the dropper thread between drops, so it never uses more than a share of a CPU core.
+    ///
+    /// See [`Throttle`]. As with the rate limits (see
+    /// [`max_drops_per_second`](Self::max_drops_per_second)), values are queued meanwhile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Throttle::Every`] has 0 values, or if [`Throttle::DutyCycle`] is not between 1
+    /// and 100.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder, Throttle};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .throttle(Throttle::DutyCycle(25))
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// ```
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        match throttle {
+            Throttle::Every { values, .. } => {
+                assert!(
+                    values > 0,
+                    "The throttle must allow some drops between pauses"
+                )
+            }
+            Throttle::DutyCycle(percent) => assert!(
+                (1..=100).contains(&percent),
+                "The duty cycle must be a percentage between 1 and 100"
+            ),
+        }
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Drop the values only when `is_idle` returns `true`, checking it again every `interval`
+    /// otherwise.
+    ///
+    /// `is_idle` is called by the dropper thread before each message. It can check the load of
+    /// the system, or the state of the application (no user input for a while, ...), so that the
+    /// cleanup stays invisible. Values are queued meanwhile, and flushes wait for the system to be
+    /// idle. Shutting down or dropping the last handle of the dropper drops the remaining values
+    /// without checking.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// let busy = Arc::new(AtomicBool::new(true));
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .drop_when_idle(Duration::from_millis(10), {
+    ///         let busy = Arc::clone(&busy);
+    ///         move || !busy.load(Ordering::Relaxed)
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// // The user is done: the value is dropped.
+    /// busy.store(false, Ordering::Relaxed);
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    pub fn drop_when_idle(
+        mut self,
+        interval: Duration,
+        is_idle: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.idle_probe = Some(IdleProbe {
+            interval,
+            is_idle: Box::new(is_idle),
+        });
+        self
+    }
+
+    /// Bound how long values can be deferred by [`Dropper::pause`] or
+    /// [`drop_when_idle`](Self::drop_when_idle).
+    ///
+    /// Once the dropper thread has deferred values for `max_deferral`, it drops the values queued
+    /// so far, then starts deferring the next ones again, even if values are still being sent.
+    /// Deferral then never becomes an unbounded backlog.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// # use std::time::Duration;
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .max_deferral(Duration::from_millis(10))
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.pause();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// // Dropped even if the dropper is never resumed.
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    pub fn max_deferral(mut self, max_deferral: Duration) -> Self {
+        self.max_deferral = Some(max_deferral);
+        self
+    }
+
+    /// Call `on_stuck` when a drop has taken longer than `deadline`.
+    ///
+    /// A destructor which deadlocks (or waits for an unresponsive resource) blocks the dropper
+    /// thread, its flushes and its shutdown. The drops are checked by a thread of the watchdog,
+    /// which calls `on_stuck` once per stuck drop, without stopping it: `on_stuck` can log it,
+    /// record a metric, or abort the process. The other stuck drops are reported once it returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// # use std::time::Duration;
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .watchdog(Duration::from_secs(5), |stuck| {
+    ///         eprintln!(
+    ///             "Dropping {} value(s) of type {} is stuck for {:?}",
+    ///             stuck.count, stuck.type_name, stuck.elapsed
+    ///         );
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// ```
+    pub fn watchdog(
+        mut self,
+        deadline: Duration,
+        on_stuck: impl Fn(&StuckDrop) + Send + Sync + 'static,
+    ) -> Self {
+        self.watchdog = Some(Watchdog::new(deadline, Box::new(on_stuck)));
+        self
+    }
+
+    /// Record the time spent dropping each value in a histogram whose buckets end at `bounds`,
+    /// retrieved with [`Dropper::stats`] (see [`LatencyHistogram`](crate::LatencyHistogram)).
+    ///
+    /// The histogram shows which drops are worth sending to the dropper, and how much time it
+    /// saves to the threads sending them. Timing the drops has a small cost, the histogram is
+    /// not recorded by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bounds` is empty.
+    pub fn latency_histogram(mut self, bounds: impl IntoIterator<Item = Duration>) -> Self {
+        let bounds: Vec<Duration> = bounds.into_iter().collect();
+        assert!(!bounds.is_empty(), "bounds must not be empty");
+        self.latency_buckets = Some(bounds);
+        self
+    }
+
+    /// Keep the `count` slowest drops, retrieved with [`Dropper::stats`] (see
+    /// [`SlowDrop`](crate::SlowDrop)).
+    ///
+    /// The slowest drops tell which types are worth sending to the dropper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// # use std::collections::HashMap;
+    /// let dropper: Dropper<HashMap<u32, String>> = DropperBuilder::new()
+    ///     .track_slowest(10)
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(HashMap::new());
+    /// dropper.dropout((0..10_000).map(|i| (i, i.to_string())).collect());
+    /// dropper.flush();
+    /// let slowest = dropper.stats().slowest;
+    /// assert_eq!(slowest.len(), 2);
+    /// assert!(slowest[0].duration >= slowest[1].duration);
+    /// ```
+    pub fn track_slowest(mut self, count: usize) -> Self {
+        assert!(count > 0, "count must be at least 1");
+        self.track_slowest = Some(count);
+        self
+    }
+
+    /// Emit the metrics of the dropper through the [`metrics`](https://docs.rs/metrics) facade,
+    /// so they are exported by the recorder of the application:
+    ///
+    /// - `dropout_pending` (gauge): number of values sent and not yet dropped,
+    /// - `dropout_enqueued_total` (counter): number of values sent to the queue,
+    /// - `dropout_dropped_total` (counter): number of values dropped by the dropper threads,
+    /// - `dropout_dropped_inline_total` (counter): number of values dropped in the sending
+    ///   thread,
+    /// - `dropout_rejected_total` (counter): number of values refused because the queue was
+    ///   full, with [`OverflowPolicy::Reject`](crate::OverflowPolicy::Reject),
+    /// - `dropout_drop_duration_seconds` (histogram): time spent dropping each value.
+    ///
+    /// The metrics are labelled with `dropper`, the [`name`](Self::name) of the dropper.
+    /// They are registered when the dropper is built: the recorder must be installed before.
+    ///
+    /// Available with the `metrics` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .name("cache-dropper")
+    ///     .metrics()
+    ///     .build()
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self) -> Self {
+        self.metrics = true;
+        self
+    }
+
+    /// Use tokio mpsc channels as the queues, when the consumers are tasks.
+    ///
+    /// With a bounded queue, [`AsyncDropper::dropout`](crate::AsyncDropper::dropout) then waits
+    /// for room as a tokio sender does: the producers are woken up in turn as the consumer frees
+    /// slots, composing with the other tokio channels of the application.
+    /// The synchronous sending methods still block the current thread (or give up) when the queue
+    /// is full. A capacity of 0 is rounded to 1, as tokio channels cannot be rendezvous ones.
+    ///
+    /// This only applies to droppers built with [`build_with`](Self::build_with) (as an
+    /// [`AsyncDropper`](crate::AsyncDropper)): dedicated dropper threads wait on their queues,
+    /// which are crossbeam channels.
+    ///
+    /// Available with the `tokio` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{AsyncDropper, DropperBuilder};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let builder = DropperBuilder::new().capacity(4).tokio_channel();
+    /// let dropper = AsyncDropper::with_builder(builder).unwrap();
+    /// for i in 0..100 {
+    ///     dropper.dropout(vec![i; 1024]).await;
+    /// }
+    /// dropper.flush().await;
+    /// assert_eq!(dropper.pending(), 0);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn tokio_channel(mut self) -> Self {
+        self.tokio_channel = true;
+        self
+    }
+
+    /// Create the dropper, returning an error if the dropper thread cannot be created.
+    pub fn build<T: Send + 'static>(self) -> io::Result<Dropper<T>> {
+        inner::Dropper::new(self).map(|d| Dropper::from_inner(Arc::new(d)))
+    }
+
+    /// Create the dropper, its consumers being run by `spawner` instead of dedicated threads.
+    ///
+    /// The thread name, stack size, priority and affinity are not used (see [`ThreadSpawner`](crate::ThreadSpawner)
+    /// to configure the threads).
+    /// Unless `spawner` is a [`ThreadSpawner`](crate::ThreadSpawner), the consumers are not
+    /// waited for when the last handle of the dropper is dropped, see [`Spawner`].
+    /// Returns the error of `spawner` if it fails to spawn a consumer.
+    pub fn build_with<T: Send + 'static>(self, spawner: impl Spawner) -> io::Result<Dropper<T>> {
+        inner::Dropper::with_spawn(self, |consumer| spawner.spawn(consumer))
+            .map(|d| Dropper::from_inner(Arc::new(d)))
+    }
+}
+
+impl Default for DropperBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
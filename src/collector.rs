@@ -0,0 +1,157 @@
+use crate::inner::StatsSource;
+use crate::LatencyHistogram;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::{Bucket, Counter, Gauge, Histogram, Metric, MetricFamily, MetricType};
+use std::collections::HashMap;
+
+/// The metrics of a dropper: name, help and type. The same as with the `metrics` feature (see
+/// [`DropperBuilder::metrics`](crate::DropperBuilder::metrics)).
+const METRICS: [(&str, &str, MetricType); 6] = [
+    (
+        "dropout_pending",
+        "Number of values sent and not yet dropped.",
+        MetricType::GAUGE,
+    ),
+    (
+        "dropout_enqueued_total",
+        "Number of values sent to the queue.",
+        MetricType::COUNTER,
+    ),
+    (
+        "dropout_dropped_total",
+        "Number of values dropped by the dropper threads.",
+        MetricType::COUNTER,
+    ),
+    (
+        "dropout_dropped_inline_total",
+        "Number of values dropped in the sending thread.",
+        MetricType::COUNTER,
+    ),
+    (
+        "dropout_rejected_total",
+        "Number of values refused because the queue was full.",
+        MetricType::COUNTER,
+    ),
+    (
+        "dropout_drop_duration_seconds",
+        "Time spent dropping each value.",
+        MetricType::HISTOGRAM,
+    ),
+];
+
+/// A [`prometheus`](https://docs.rs/prometheus) collector exposing the stats of a dropper.
+///
+/// Created with [`Dropper::prometheus_collector`](crate::Dropper::prometheus_collector), and
+/// registered in a [`prometheus::Registry`]. Each scrape takes a snapshot of the
+/// [`stats`](crate::Dropper::stats) of the dropper, exposed as:
+///
+/// - `dropout_pending` (gauge): number of values sent and not yet dropped,
+/// - `dropout_enqueued_total` (counter): number of values sent to the queue,
+/// - `dropout_dropped_total` (counter): number of values dropped by the dropper threads,
+/// - `dropout_dropped_inline_total` (counter): number of values dropped in the sending thread,
+/// - `dropout_rejected_total` (counter): number of values refused because the queue was full,
+///   with [`OverflowPolicy::Reject`](crate::OverflowPolicy::Reject),
+/// - `dropout_drop_duration_seconds` (histogram): time spent dropping each value, if recorded
+///   (see [`DropperBuilder::latency_histogram`](crate::DropperBuilder::latency_histogram)).
+///
+/// The metrics are labelled with `dropper`, the [`name`](crate::DropperBuilder::name) of the
+/// dropper: they are the ones of the `metrics` feature. The collector doesn't keep the dropper
+/// open, its last stats are exposed once it is closed.
+///
+/// Available with the `prometheus` feature.
+pub struct DropperCollector {
+    source: StatsSource,
+    descs: Vec<Desc>,
+}
+
+impl DropperCollector {
+    pub(crate) fn new(source: StatsSource) -> Self {
+        let labels = HashMap::from([("dropper".to_owned(), source.name().to_owned())]);
+        let descs = METRICS
+            .iter()
+            .map(|(name, help, _)| {
+                Desc::new(
+                    name.to_string(),
+                    help.to_string(),
+                    Vec::new(),
+                    labels.clone(),
+                )
+                .expect("The metric names and labels are valid")
+            })
+            .collect();
+        Self { source, descs }
+    }
+
+    /// The family of the metric `index` of `METRICS`, with `metric` as its only metric.
+    fn family(&self, index: usize, mut metric: Metric) -> MetricFamily {
+        let desc = &self.descs[index];
+        metric.set_label(desc.const_label_pairs.clone());
+        let mut family = MetricFamily::default();
+        family.set_name(desc.fq_name.clone());
+        family.set_help(desc.help.clone());
+        family.set_field_type(METRICS[index].2);
+        family.set_metric(vec![metric]);
+        family
+    }
+}
+
+impl Collector for DropperCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let stats = self.source.stats();
+        let mut families = vec![
+            self.family(0, gauge(stats.pending as f64)),
+            self.family(1, counter(stats.enqueued)),
+            self.family(2, counter(stats.dropped)),
+            self.family(3, counter(stats.dropped_inline)),
+            self.family(4, counter(stats.rejected)),
+        ];
+        if let Some(latency) = &stats.latency {
+            families.push(self.family(5, histogram(latency)));
+        }
+        families
+    }
+}
+
+fn gauge(value: f64) -> Metric {
+    let mut gauge = Gauge::default();
+    gauge.set_value(value);
+    let mut metric = Metric::default();
+    metric.set_gauge(gauge);
+    metric
+}
+
+fn counter(value: u64) -> Metric {
+    let mut counter = Counter::default();
+    counter.set_value(value as f64);
+    let mut metric = Metric::default();
+    metric.set_counter(counter);
+    metric
+}
+
+/// The histogram of `latency`, whose counts are per bucket while prometheus ones are cumulative.
+fn histogram(latency: &LatencyHistogram) -> Metric {
+    let mut cumulative = 0;
+    let buckets = latency
+        .bounds
+        .iter()
+        .zip(&latency.counts)
+        .map(|(bound, count)| {
+            cumulative += count;
+            let mut bucket = Bucket::default();
+            bucket.set_upper_bound(bound.as_secs_f64());
+            bucket.set_cumulative_count(cumulative);
+            bucket
+        })
+        .collect();
+    let mut histogram = Histogram::default();
+    histogram.set_sample_count(latency.count());
+    histogram.set_sample_sum(latency.total.as_secs_f64());
+    histogram.set_bucket(buckets);
+    let mut metric = Metric::default();
+    metric.set_histogram(histogram);
+    metric
+}
@@ -0,0 +1,101 @@
+//! Compatibility with the API of [defer-drop](https://docs.rs/defer-drop).
+//!
+//! Projects using defer-drop can migrate by replacing `use defer_drop::DeferDrop;` by
+//! `use dropout::compat::DeferDrop;`, then move to the rest of the dropout API when needed.
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+
+/// A value sent to the process-global dropper thread when the wrapper is dropped.
+///
+/// This mirrors `defer_drop::DeferDrop`: all the values are dropped by one background thread,
+/// the global dropper (see [`global`](crate::global)). Unlike defer-drop, values still pending
+/// are dropped before the process exits normally.
+///
+/// To use a dedicated dropper thread, see [`Deferred`](crate::Deferred).
+///
+/// # Example
+///
+/// ```
+/// use dropout::compat::DeferDrop;
+///
+/// let values = DeferDrop::new(vec![vec![0u8; 1024]; 100]);
+/// assert_eq!(values.len(), 100);
+/// drop(values); // The vec is dropped in the global dropper thread.
+/// ```
+pub struct DeferDrop<T: Send + 'static> {
+    inner: ManuallyDrop<T>,
+}
+
+impl<T: Send + 'static> DeferDrop<T> {
+    /// Wrap `value`, to send it to the global dropper thread when the wrapper is dropped.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: ManuallyDrop::new(value),
+        }
+    }
+
+    /// Take the value back, it will not be sent to the dropper thread.
+    ///
+    /// This is an associated function (`DeferDrop::into_inner(this)`) to not conflict with
+    /// methods of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::compat::DeferDrop;
+    /// let values = DeferDrop::new(vec![1, 2, 3]);
+    /// let values: Vec<i32> = DeferDrop::into_inner(values);
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn into_inner(this: Self) -> T {
+        let mut this = ManuallyDrop::new(this);
+        // SAFETY: `this` is not dropped, so the value is taken only once.
+        unsafe { ManuallyDrop::take(&mut this.inner) }
+    }
+}
+
+impl<T: Send + 'static> Deref for DeferDrop<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Send + 'static> DerefMut for DeferDrop<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: Send + 'static + Clone> Clone for DeferDrop<T> {
+    fn clone(&self) -> Self {
+        Self::new(T::clone(&self.inner))
+    }
+}
+
+impl<T: Send + 'static + Default> Default for DeferDrop<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Send + 'static + fmt::Debug> fmt::Debug for DeferDrop<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DeferDrop").field(&*self.inner).finish()
+    }
+}
+
+impl<T: Send + 'static> Drop for DeferDrop<T> {
+    fn drop(&mut self) {
+        // SAFETY: `inner` is never used again.
+        let value = unsafe { ManuallyDrop::take(&mut self.inner) };
+        crate::dropout(value);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// State shared by a value sent to a dropper and the handles waiting for its drop.
+#[derive(Default)]
+struct Completion {
+    done: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+    dropped: Condvar,
+}
+
+impl Completion {
+    fn lock(&self) -> MutexGuard<'_, Option<Waker>> {
+        self.waker.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+
+    fn new() -> (Arc<Self>, Completer) {
+        let completion = Arc::new(Self::default());
+        (Arc::clone(&completion), Completer(completion))
+    }
+}
+
+/// Mark the value as dropped when dropped, even if the drop of the value panicked.
+pub(crate) struct Completer(Arc<Completion>);
+
+impl Drop for Completer {
+    fn drop(&mut self) {
+        let waker = {
+            let mut waker = self.0.lock();
+            self.0.done.store(true, Ordering::SeqCst);
+            self.0.dropped.notify_all();
+            waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// A future completing once a value sent to a dropper has been dropped.
+///
+/// Created with [`Dropper::dropout_notified`](crate::Dropper::dropout_notified).
+/// Dropping the future doesn't cancel the drop of the value.
+#[must_use = "futures do nothing unless awaited"]
+pub struct Dropped(Arc<Completion>);
+
+impl Dropped {
+    pub(crate) fn new() -> (Self, Completer) {
+        let (completion, completer) = Completion::new();
+        (Self(completion), completer)
+    }
+
+    /// Returns `true` if the value has been dropped.
+    #[inline]
+    pub fn is_dropped(&self) -> bool {
+        self.0.is_done()
+    }
+}
+
+impl Future for Dropped {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_dropped() {
+            return Poll::Ready(());
+        }
+        *self.0.lock() = Some(cx.waker().clone());
+        // Check again, the value may have been dropped before the waker was set.
+        match self.is_dropped() {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// A handle to wait for a value sent to a dropper to be dropped.
+///
+/// This is the blocking counterpart of [`Dropped`].
+/// Created with [`Dropper::dropout_tracked`](crate::Dropper::dropout_tracked).
+pub struct DropTicket(Arc<Completion>);
+
+impl DropTicket {
+    pub(crate) fn new() -> (Self, Completer) {
+        let (completion, completer) = Completion::new();
+        (Self(completion), completer)
+    }
+
+    /// Returns `true` if the value has been dropped.
+    #[inline]
+    pub fn is_dropped(&self) -> bool {
+        self.0.is_done()
+    }
+
+    /// Block until the value has been dropped.
+    pub fn wait(&self) {
+        let mut guard = self.0.lock();
+        while !self.0.is_done() {
+            guard = self
+                .0
+                .dropped
+                .wait(guard)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Block until the value has been dropped, at most `timeout`.
+    ///
+    /// Returns `false` if the timeout expired first.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.0.lock();
+        while !self.0.is_done() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            guard = self
+                .0
+                .dropped
+                .wait_timeout(guard, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        true
+    }
+}
+
+/// The sequence numbers given to the values sent with
+/// [`Dropper::dropout_seq`](crate::Dropper::dropout_seq) (or through a
+/// [`FencedDropper`](crate::FencedDropper)), and the ones dropped.
+#[derive(Default)]
+pub(crate) struct Sequences {
+    state: Mutex<SequenceState>,
+    dropped: Condvar,
+}
+
+#[derive(Default)]
+struct SequenceState {
+    /// Sequence number of the next value.
+    next: u64,
+    /// All the values of lower sequence numbers are dropped.
+    dropped_below: u64,
+    /// Values dropped before some of lower sequence numbers.
+    dropped: BTreeSet<u64>,
+}
+
+impl Sequences {
+    fn lock(&self) -> MutexGuard<'_, SequenceState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Give the next sequence number, with the guard marking it as dropped.
+    pub(crate) fn next(self: &Arc<Self>) -> (u64, SequenceGuard) {
+        let mut state = self.lock();
+        let sequence = state.next;
+        state.next += 1;
+        (sequence, SequenceGuard(Arc::clone(self), sequence))
+    }
+
+    /// Number of sequence numbers given so far.
+    pub(crate) fn given(&self) -> u64 {
+        self.lock().next
+    }
+
+    /// Block until the values up to `sequence` are dropped.
+    pub(crate) fn wait_for(&self, sequence: u64) {
+        assert!(
+            sequence < self.given(),
+            "The sequence number was not given by this dropper"
+        );
+        self.wait_below(sequence + 1);
+    }
+
+    /// Block until the values of sequence numbers lower than `end` are dropped.
+    pub(crate) fn wait_below(&self, end: u64) {
+        let mut state = self.lock();
+        while state.dropped_below < end {
+            state = self
+                .dropped
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+/// Mark a sequence number as dropped when dropped, even if the drop of the value panicked.
+pub(crate) struct SequenceGuard(Arc<Sequences>, u64);
+
+impl Drop for SequenceGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.dropped.insert(self.1);
+        let mut advanced = false;
+        while let Some(sequence) = state.dropped.first().copied() {
+            if sequence != state.dropped_below {
+                break;
+            }
+            state.dropped.pop_first();
+            state.dropped_below += 1;
+            advanced = true;
+        }
+        if advanced {
+            self.0.dropped.notify_all();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::Dropper;
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::Arc;
+
+/// A value sent to a [`Dropper`] when the wrapper is dropped.
+///
+/// `Deferred<T>` derefs to `T`, so it can be used in place of the value, and moves the drop of
+/// the value to the dropper thread without an explicit [`Dropper::dropout`] call.
+///
+/// Created with [`Dropper::wrap`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// let dropper = Dropper::new();
+/// let values = dropper.wrap(vec![1, 2, 3]);
+/// assert_eq!(values.len(), 3);
+/// drop(values); // The vec is dropped in the dropper thread.
+/// ```
+pub struct Deferred<T: Send + 'static> {
+    value: ManuallyDrop<T>,
+    dropper: Dropper<T>,
+}
+
+impl<T: Send + 'static> Deferred<T> {
+    /// Wrap `value`, to send it to `dropper` when the wrapper is dropped.
+    #[inline]
+    pub fn new(value: T, dropper: Dropper<T>) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            dropper,
+        }
+    }
+
+    /// Take the value back, it will not be sent to the dropper.
+    ///
+    /// This is an associated function (`Deferred::into_inner(deferred)`) to not conflict with
+    /// methods of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Deferred, Dropper};
+    /// let dropper = Dropper::new();
+    /// let values = dropper.wrap(vec![1, 2, 3]);
+    /// let values: Vec<i32> = Deferred::into_inner(values);
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn into_inner(this: Self) -> T {
+        let mut this = ManuallyDrop::new(this);
+        // SAFETY: `this` is not dropped, so its fields are taken only once.
+        unsafe {
+            ptr::drop_in_place(&mut this.dropper);
+            ManuallyDrop::take(&mut this.value)
+        }
+    }
+}
+
+impl<T: Send + 'static> Deref for Deferred<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Send + 'static> DerefMut for Deferred<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Send + 'static + fmt::Debug> fmt::Debug for Deferred<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Deferred").field(&*self.value).finish()
+    }
+}
+
+impl<T: Send + 'static> Drop for Deferred<T> {
+    fn drop(&mut self) {
+        // SAFETY: `value` is never used again.
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        self.dropper.dropout(value);
+    }
+}
+
+/// A shared value, sent to a [`Dropper`] when the last reference is dropped.
+///
+/// `DropArc<T>` is like an `Arc<T>` (it wraps an `Arc<Deferred<T>>`): cloning it only
+/// increments a reference count, and the value is dropped in the dropper thread when the last
+/// clone is dropped, whichever owner drops it.
+///
+/// Created with [`Dropper::wrap_arc`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// # use std::collections::HashMap;
+/// let dropper = Dropper::new();
+/// let cache = dropper.wrap_arc(HashMap::from([(1, vec![0u8; 1024])]));
+/// let reader = cache.clone();
+/// std::thread::spawn(move || assert_eq!(reader.len(), 1))
+///     .join()
+///     .unwrap();
+/// drop(cache); // Last reference: the map is dropped in the dropper thread.
+/// ```
+pub struct DropArc<T: Send + 'static>(Arc<Deferred<T>>);
+
+impl<T: Send + 'static> DropArc<T> {
+    /// Share `value`, to send it to `dropper` when the last reference is dropped.
+    #[inline]
+    pub fn new(value: T, dropper: Dropper<T>) -> Self {
+        Self(Arc::new(Deferred::new(value, dropper)))
+    }
+
+    /// Number of references to the value. See [`Arc::strong_count`].
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        Arc::strong_count(&this.0)
+    }
+
+    /// Returns `true` if the two `DropArc`s point to the same value. See [`Arc::ptr_eq`].
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl<T: Send + 'static> Deref for DropArc<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0.value
+    }
+}
+
+impl<T: Send + 'static> Clone for DropArc<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Send + 'static + fmt::Debug> fmt::Debug for DropArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DropArc").field(&**self).finish()
+    }
+}
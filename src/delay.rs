@@ -0,0 +1,117 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread;
+use std::time::Instant;
+
+/// A job to run once its deadline is reached.
+struct Entry {
+    deadline: Instant,
+    /// Order of scheduling, to run the jobs with the same deadline in order.
+    sequence: u64,
+    job: Box<dyn FnOnce() + Send>,
+}
+
+impl Entry {
+    fn key(&self) -> (Instant, u64) {
+        (self.deadline, self.sequence)
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[derive(Default)]
+struct Schedule {
+    entries: BinaryHeap<Reverse<Entry>>,
+    sequence: u64,
+}
+
+/// The process-global timer thread, holding the values sent with a delay.
+#[derive(Default)]
+struct Timer {
+    schedule: Mutex<Schedule>,
+    changed: Condvar,
+}
+
+static TIMER: OnceLock<Timer> = OnceLock::new();
+
+impl Timer {
+    fn lock(&self) -> MutexGuard<'_, Schedule> {
+        self.schedule.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run the jobs as their deadlines are reached.
+    fn run(&self) {
+        let mut schedule = self.lock();
+        loop {
+            let now = Instant::now();
+            match schedule.entries.peek() {
+                Some(Reverse(entry)) if entry.deadline <= now => {
+                    let Reverse(entry) = schedule.entries.pop().unwrap();
+                    drop(schedule);
+                    // A panicking job must not stop the other ones.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(entry.job));
+                    schedule = self.lock();
+                }
+                Some(Reverse(entry)) => {
+                    let timeout = entry.deadline - now;
+                    schedule = self
+                        .changed
+                        .wait_timeout(schedule, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0;
+                }
+                None => {
+                    schedule = self
+                        .changed
+                        .wait(schedule)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+    }
+}
+
+fn get() -> &'static Timer {
+    TIMER.get_or_init(|| {
+        thread::Builder::new()
+            .name("Dropout timer".into())
+            .spawn(|| get().run())
+            .expect("Should succeed to create the timer thread");
+        Timer::default()
+    })
+}
+
+/// Run `job` in the timer thread once `deadline` is reached.
+pub(crate) fn schedule(deadline: Instant, job: impl FnOnce() + Send + 'static) {
+    let timer = get();
+    let mut schedule = timer.lock();
+    let sequence = schedule.sequence;
+    schedule.sequence += 1;
+    schedule.entries.push(Reverse(Entry {
+        deadline,
+        sequence,
+        job: Box::new(job),
+    }));
+    drop(schedule);
+    timer.changed.notify_one();
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+#[cfg(feature = "futures")]
+use {
+    crate::inner::Observer,
+    futures_core::Stream,
+    std::collections::VecDeque,
+    std::pin::Pin,
+    std::sync::{Arc, Mutex, MutexGuard, PoisonError},
+    std::task::{Context, Poll, Waker},
+};
+
+/// A drop done by a dropper thread.
+///
+/// A message sent to the dropper (a value, a batch of values, a collection dropped a chunk at a
+/// time, ...) is reported as one event once dropped.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DropEvent {
+    /// Sequence number of the drop, in the order of the drops.
+    pub id: u64,
+    /// Name of the type of the dropped values, see [`std::any::type_name`].
+    ///
+    /// Type erased values (sent to an [`AnyDropper`](crate::AnyDropper), a pool or a shared
+    /// thread) keep the name of their type, except those sent already boxed with
+    /// [`AnyDropper::dropout_box`](crate::AnyDropper::dropout_box), named `dyn Any + Send`.
+    /// A closure sent with `defer` is named after the type of the closure.
+    pub type_name: &'static str,
+    /// Number of values dropped.
+    pub count: usize,
+    /// Time spent dropping the values.
+    pub duration: Duration,
+}
+
+/// Maximum number of events a `DropEvents` buffers.
+#[cfg(feature = "futures")]
+const CAPACITY: usize = 1024;
+
+/// A stream of the drops done by a dropper thread.
+///
+/// Created with [`Dropper::events`](crate::Dropper::events). The stream yields the drops done after
+/// its creation and ends when the dropper threads stop. If the events are not consumed, at most
+/// 1024 of them are buffered, the oldest ones being discarded.
+///
+/// Available with the `futures` feature.
+#[cfg(feature = "futures")]
+pub struct DropEvents(Arc<Mutex<EventQueue>>);
+
+#[cfg(feature = "futures")]
+#[derive(Default)]
+struct EventQueue {
+    events: VecDeque<DropEvent>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+#[cfg(feature = "futures")]
+fn lock(queue: &Mutex<EventQueue>) -> MutexGuard<'_, EventQueue> {
+    queue.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(feature = "futures")]
+impl DropEvents {
+    /// Create a stream, with the observer feeding it.
+    pub(crate) fn new() -> (Self, Observer) {
+        let queue = Arc::new(Mutex::new(EventQueue::default()));
+        let publisher = Publisher(Arc::clone(&queue));
+        (Self(queue), Box::new(move |event| publisher.publish(event)))
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Stream for DropEvents {
+    type Item = DropEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DropEvent>> {
+        let mut queue = lock(&self.0);
+        if let Some(event) = queue.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+        queue.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Feed a `DropEvents`, ending it when dropped.
+#[cfg(feature = "futures")]
+struct Publisher(Arc<Mutex<EventQueue>>);
+
+#[cfg(feature = "futures")]
+impl Publisher {
+    /// Push an event, returning `false` if the stream is gone.
+    fn publish(&self, event: &DropEvent) -> bool {
+        if Arc::strong_count(&self.0) == 1 {
+            return false;
+        }
+        let mut queue = lock(&self.0);
+        if queue.events.len() == CAPACITY {
+            queue.events.pop_front();
+        }
+        queue.events.push_back(event.clone());
+        if let Some(waker) = queue.waker.take() {
+            drop(queue);
+            waker.wake();
+        }
+        true
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let mut queue = lock(&self.0);
+        queue.closed = true;
+        if let Some(waker) = queue.waker.take() {
+            drop(queue);
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::completion::Sequences;
+use crate::Dropper;
+use std::sync::Arc;
+
+/// A point in the values sent through a [`FencedDropper`], to wait for the ones sent before it.
+///
+/// Created with [`FencedDropper::fence`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fence(u64);
+
+/// A handle of a [`Dropper`] able to wait for the values sent through it, and only them.
+///
+/// [`Dropper::flush`] waits for all the values sent to the dropper, by all its handles.
+/// A `FencedDropper` tracks the values sent through it: [`fence_wait`](Self::fence_wait) waits
+/// for the values sent before a [`fence`](Self::fence) to be dropped, without waiting for the
+/// values of the other handles. Taking a fence doesn't send anything to the dropper.
+///
+/// Each value is sent boxed with a guard marking it as dropped (see [`Dropper::defer`]), even if
+/// the dropper threads drop it out of order. Clones of a `FencedDropper` share the same values.
+///
+/// Created with [`Dropper::fenced`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// let dropper = Dropper::new();
+/// let cache = dropper.fenced();
+/// cache.dropout(vec![0u8; 1024]);
+/// dropper.dropout(vec![1u8; 1024 * 1024]);
+/// let fence = cache.fence();
+/// // Waits for the drops of the cache only.
+/// cache.fence_wait(fence);
+/// ```
+pub struct FencedDropper<T: Send + 'static> {
+    dropper: Dropper<T>,
+    sequences: Arc<Sequences>,
+}
+
+impl<T: Send + 'static> FencedDropper<T> {
+    #[inline]
+    pub(crate) fn new(dropper: Dropper<T>) -> Self {
+        Self {
+            dropper,
+            sequences: Arc::default(),
+        }
+    }
+
+    /// Send a value to be dropped in another thread. See [`Dropper::dropout`].
+    pub fn dropout(&self, to_drop: T) {
+        let (_, guard) = self.sequences.next();
+        self.dropper.defer(move || {
+            let _guard = guard;
+            drop(to_drop);
+        });
+    }
+
+    /// Get a fence after the values sent so far through this handle.
+    #[inline]
+    pub fn fence(&self) -> Fence {
+        Fence(self.sequences.given())
+    }
+
+    /// Block until the values sent through this handle before `fence` are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fence` was not taken on this handle (or a clone).
+    pub fn fence_wait(&self, fence: Fence) {
+        assert!(
+            fence.0 <= self.sequences.given(),
+            "The fence was not taken on this handle"
+        );
+        self.sequences.wait_below(fence.0);
+    }
+
+    /// The underlying [`Dropper`]. Values sent through it are not tracked by the fences.
+    #[inline]
+    pub fn as_dropper(&self) -> &Dropper<T> {
+        &self.dropper
+    }
+}
+
+impl<T: Send + 'static> Clone for FencedDropper<T> {
+    fn clone(&self) -> Self {
+        Self {
+            dropper: self.dropper.clone(),
+            sequences: Arc::clone(&self.sequences),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{AnyDropper, DropperRegistry};
+use std::any::Any;
+use std::os::raw::c_int;
+use std::sync::OnceLock;
+
+/// The process-global dropper thread, shared by [`global`] and [`Dropper::shared`](crate::Dropper::shared).
+struct Global {
+    registry: DropperRegistry,
+    any: AnyDropper,
+}
+
+static GLOBAL: OnceLock<Global> = OnceLock::new();
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> c_int;
+}
+
+/// Flush the global dropper when the process exits.
+///
+/// The flush is skipped if the process exits from the dropper thread itself (in a `Drop` impl):
+/// the thread would wait for its own queue forever.
+///
+/// It runs in the thread calling `exit`, while the other threads (the dropper thread included)
+/// keep running until the exit handlers return, so waiting for the dropper thread from there
+/// makes progress. `GLOBAL` is a static, never dropped, so it is still valid then. A panic cannot
+/// unwind out of an `extern "C"` function: the process aborts instead.
+extern "C" fn flush_global() {
+    if let Some(global) = GLOBAL.get() {
+        if !global.registry.is_dropper_thread() {
+            global.registry.flush();
+        }
+    }
+}
+
+fn get() -> &'static Global {
+    GLOBAL.get_or_init(|| {
+        let registry = DropperRegistry::new();
+        let any = registry.dropper::<Box<dyn Any + Send>>().erase();
+        // SAFETY: `atexit` only stores the function pointer, `flush_global` is a valid
+        // `extern "C" fn()` which doesn't unwind, and can block from the exit path (see above).
+        let registered = unsafe { atexit(flush_global) } == 0;
+        // The values pending at exit would silently not be dropped.
+        assert!(registered, "Should succeed to register the flush at exit");
+        Global { registry, any }
+    })
+}
+
+/// The process-global dropper, created on first use.
+///
+/// Values sent to it are dropped before the process exits normally (returning from `main`
+/// or calling [`std::process::exit`]), unless it exits from the global dropper thread.
+///
+/// # Panics
+///
+/// Panics if the dropper thread cannot be created, or if its flush at exit cannot be registered
+/// (the C library has no room left for exit handlers).
+pub fn global() -> &'static AnyDropper {
+    &get().any
+}
+
+/// Send a value to be dropped in the process-global dropper thread.
+///
+/// This avoids creating and passing around a [`Dropper`](crate::Dropper) for occasional drops.
+/// See [`global`].
+///
+/// # Example
+///
+/// ```
+/// # use std::collections::HashMap;
+/// let heavy: HashMap<usize, Vec<usize>> = (0..1000).map(|v| (v, vec![v])).collect();
+/// dropout::dropout(heavy);
+/// dropout::dropout(String::from("Hello"));
+/// dropout::global().flush();
+/// ```
+#[inline]
+pub fn dropout<U: Send + 'static>(to_drop: U) {
+    global().dropout(to_drop)
+}
+
+/// The registry of the process-global dropper thread.
+pub(crate) fn registry() -> &'static DropperRegistry {
+    &get().registry
+}
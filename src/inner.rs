@@ -0,0 +1,2835 @@
+use crate::affinity::Affinity;
+use crate::completion::Sequences;
+use crate::rate::{IdleProbe, Pacer};
+use crate::stats::{Counters, DropperStats};
+use crate::watchdog::Watchdog;
+use crate::{
+    delay, DropEvent, DropOrder, DropperBuilder, OverflowPolicy, PanicPolicy, Priority,
+    ShutdownError, ShutdownOutcome, ThreadPriority, Watermarks,
+};
+use crossbeam_channel::{
+    bounded, unbounded, Receiver, RecvError, RecvTimeoutError, Select, SendTimeoutError, Sender,
+    TryRecvError, TrySendError,
+};
+use std::any;
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::io;
+use std::mem;
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::process;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::task::{ready, Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "tokio")]
+use tokio::sync::{mpsc, Semaphore};
+
+/// Message sent to the dropper thread.
+enum Message<T> {
+    /// A value to drop.
+    Drop(T),
+    /// A type erased value to drop, with the name of its type.
+    Erased(Box<dyn Any + Send>, &'static str),
+    /// Values to drop, sent at once.
+    Batch(Vec<T>),
+    /// A type erased batch of values to drop, with the number of values and the name of their
+    /// type.
+    ErasedBatch(Box<dyn Any + Send>, usize, &'static str),
+    /// A collection to drop a chunk at a time.
+    Chunked(Box<dyn DropChunks>),
+    /// A future to run to completion, with the name of its type.
+    Cleanup(Cleanup, &'static str),
+    /// A message of values, with their size in bytes for the rate limit.
+    Sized(Box<Message<T>>, usize),
+    /// A marker to acknowledge once all previous messages are handled.
+    Flush(Arc<FlushMarker>),
+    /// Ask the dropper thread to stop once all previous messages are handled.
+    Close,
+    /// Wake up a dropper thread, to wait on the queues added since it started waiting.
+    Wake,
+}
+
+impl<T> Message<T> {
+    /// Number of values to drop in the message.
+    fn count(&self) -> usize {
+        match self {
+            Self::Drop(_) | Self::Erased(..) | Self::Chunked(_) | Self::Cleanup(..) => 1,
+            Self::Batch(values) => values.len(),
+            Self::ErasedBatch(_, count, _) => *count,
+            Self::Sized(message, _) => message.count(),
+            Self::Flush(_) | Self::Close | Self::Wake => 0,
+        }
+    }
+
+    /// Returns `true` if the message must be handled after all the previous ones.
+    fn is_barrier(&self) -> bool {
+        matches!(self, Self::Flush(_) | Self::Close)
+    }
+
+    fn into_value(self) -> T {
+        match self {
+            Self::Drop(value) => value,
+            Self::Sized(message, _) => message.into_value(),
+            _ => unreachable!("Only values are given back to the caller"),
+        }
+    }
+
+    fn into_erased(self) -> Box<dyn Any + Send> {
+        match self {
+            Self::Erased(value, _) => value,
+            Self::Sized(message, _) => message.into_erased(),
+            _ => unreachable!("Only values are given back to the caller"),
+        }
+    }
+}
+
+impl<T: Send + 'static> Message<T> {
+    /// Box the value, to send it to a thread shared with droppers of other types.
+    fn boxed(self) -> Message<Box<dyn Any + Send>> {
+        match self {
+            Self::Drop(value) => Message::Erased(Box::new(value), any::type_name::<T>()),
+            Self::Erased(value, type_name) => Message::Erased(value, type_name),
+            Self::Batch(values) => {
+                let count = values.len();
+                Message::ErasedBatch(Box::new(values), count, any::type_name::<T>())
+            }
+            Self::ErasedBatch(values, count, type_name) => {
+                Message::ErasedBatch(values, count, type_name)
+            }
+            Self::Chunked(chunks) => Message::Chunked(chunks),
+            Self::Cleanup(cleanup, type_name) => Message::Cleanup(cleanup, type_name),
+            Self::Sized(message, bytes) => Message::Sized(Box::new(message.boxed()), bytes),
+            Self::Flush(marker) => Message::Flush(marker),
+            Self::Close => Message::Close,
+            Self::Wake => Message::Wake,
+        }
+    }
+
+    /// Revert `boxed`, `is_drop` telling if the message was a `Drop` one.
+    fn unboxed(message: Message<Box<dyn Any + Send>>, is_drop: bool) -> Self {
+        match message {
+            Message::Erased(value, _) if is_drop => Self::Drop(*value.downcast().unwrap()),
+            Message::Erased(value, type_name) => Self::Erased(value, type_name),
+            Message::Drop(value) => Self::Erased(value, any::type_name::<dyn Any + Send>()),
+            Message::ErasedBatch(values, count, type_name) => match values.downcast() {
+                Ok(values) => Self::Batch(*values),
+                Err(values) => Self::ErasedBatch(values, count, type_name),
+            },
+            Message::Batch(values) => {
+                let count = values.len();
+                let type_name = any::type_name::<Box<dyn Any + Send>>();
+                Self::ErasedBatch(Box::new(values), count, type_name)
+            }
+            Message::Chunked(chunks) => Self::Chunked(chunks),
+            Message::Cleanup(cleanup, type_name) => Self::Cleanup(cleanup, type_name),
+            Message::Sized(message, bytes) => {
+                Self::Sized(Box::new(Self::unboxed(*message, is_drop)), bytes)
+            }
+            Message::Flush(marker) => Self::Flush(marker),
+            Message::Close => Self::Close,
+            Message::Wake => Self::Wake,
+        }
+    }
+}
+
+/// A collection dropped a chunk at a time.
+pub trait DropChunks: Send {
+    /// Drop the next chunk, returning `true` if the collection is now fully dropped.
+    fn drop_chunk(&mut self) -> bool;
+
+    /// Name of the type of the remaining items.
+    fn type_name(&self) -> &'static str;
+}
+
+/// The remaining items of a collection, dropped `size` at a time.
+struct Chunks<I> {
+    items: I,
+    size: usize,
+}
+
+impl<I: Iterator + Send> DropChunks for Chunks<I> {
+    fn drop_chunk(&mut self) -> bool {
+        self.items.by_ref().take(self.size).count() < self.size
+    }
+
+    fn type_name(&self) -> &'static str {
+        any::type_name::<I>()
+    }
+}
+
+/// A future cleaning up a value, run to completion when dropped if it was not polled to it.
+pub struct Cleanup(Option<Pin<Box<dyn Future<Output = ()> + Send>>>);
+
+impl Cleanup {
+    /// Poll the future, which is dropped if it panics.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(mut future) = self.0.take() else {
+            return Poll::Ready(());
+        };
+        let poll = future.as_mut().poll(cx);
+        if poll.is_pending() {
+            self.0 = Some(future);
+        }
+        poll
+    }
+}
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        if let Some(future) = self.0.take() {
+            block_on(future);
+        }
+    }
+}
+
+/// Flush marker, one is sent to each dropper thread.
+///
+/// A dropper thread receiving a marker waits for the other threads to receive theirs, so all
+/// the values sent before the markers have been dropped once they are all received.
+/// As a waiting thread cannot receive another marker, each marker is received by a different thread.
+/// A consumer task waits without blocking its executor, which may run the other consumers.
+struct FlushMarker {
+    ack: Sender<()>,
+    /// Number of markers received.
+    arrived: AtomicUsize,
+    /// Number of markers sent.
+    sent: usize,
+}
+
+impl FlushMarker {
+    /// Count a marker as received.
+    fn arrive(&self, shared: &Shared) {
+        self.arrived.fetch_add(1, Ordering::SeqCst);
+        shared.notify();
+    }
+
+    /// Returns `true` once the markers have been received by all the running threads.
+    fn all_arrived(&self, shared: &Shared) -> bool {
+        let expected = self.sent.min(shared.running.load(Ordering::SeqCst));
+        self.arrived.load(Ordering::SeqCst) >= expected
+    }
+
+    fn acknowledge(&self, shared: &Shared) {
+        let _ = self.ack.send(());
+        // Wake up the tasks waiting for the acknowledgement.
+        shared.notify();
+    }
+}
+
+/// Type erased loop of a dropper thread, receiving and handling messages until closed.
+type Runner = Arc<dyn Fn(&Shared) + Send + Sync>;
+
+/// Type erased step of a consumer running as a task, handling at most one message.
+type Stepper = Arc<dyn Fn(&Shared, &mut Ongoing<Arc<Shared>>) -> Step + Send + Sync>;
+
+/// Why the dropper threads defer the messages.
+enum Deferral {
+    /// The dropper is paused, until resumed or the deadline if any.
+    Paused(Option<Instant>),
+    /// The system is busy, until the given instant to check the idle probe again.
+    Busy(Instant),
+}
+
+/// Outcome of a consumer step.
+enum Step {
+    /// A message has been handled.
+    Handled,
+    /// The queue is empty.
+    Empty,
+    /// The rate limit, throttle or idle probe delays the messages until the given instant.
+    Throttled(Instant),
+    /// The dropper is paused, until resumed or the given instant if any.
+    Paused(Option<Instant>),
+    /// A flush marker has been received, to acknowledge once the other consumers have theirs.
+    Flushing(Arc<FlushMarker>),
+    /// The consumer must stop.
+    Closed,
+}
+
+/// Called after each observed drop, unregistered when returning `false`.
+pub type Observer = Box<dyn FnMut(&DropEvent) -> bool + Send>;
+
+/// Type erased interface of a `Dropper<T>`, used to send values of any type to its thread.
+pub trait ErasedDropper: Send + Sync {
+    fn dropout_erased(&self, to_drop: Box<dyn Any + Send>, type_name: &'static str);
+    fn defer_cleanup(&self, cleanup: Cleanup, type_name: &'static str);
+    fn try_dropout_erased(
+        &self,
+        to_drop: Box<dyn Any + Send>,
+        type_name: &'static str,
+    ) -> Result<(), Box<dyn Any + Send>>;
+    fn flush(&self);
+    fn flush_timeout(&self, timeout: Duration) -> bool;
+    fn pending(&self) -> usize;
+    fn stats(&self) -> DropperStats;
+    fn is_healthy(&self) -> bool;
+    fn shutdown(&self) -> Result<(), ShutdownError>;
+}
+
+/// State shared between the handles and the dropper thread.
+#[derive(Default)]
+struct Shared {
+    /// Number of values sent and not yet received by the dropper thread.
+    queued: AtomicUsize,
+    /// Number of values sent and not yet dropped.
+    pending: AtomicUsize,
+    /// Number of messages received by the dropper threads, to detect room in the queue.
+    received: AtomicUsize,
+    /// Set when all the dropper threads have stopped.
+    finished: AtomicBool,
+    /// Set while the dropper threads must not drop values.
+    paused: AtomicBool,
+    /// Set when the last handle has been dropped: the remaining values are dropped at once.
+    released: AtomicBool,
+    /// With `DropperBuilder::drop_when_idle`, the values are dropped only when the system is idle.
+    idle_probe: Option<IdleProbe>,
+    /// How long the values can be deferred by a pause or the idle probe.
+    max_deferral: Option<Duration>,
+    /// When the values started to be deferred.
+    deferred_since: Mutex<Option<Instant>>,
+    /// Number of values queued before the end of the last deferral, to drop before deferring again.
+    draining: AtomicUsize,
+    /// Reports the drops taking too long, with `DropperBuilder::watchdog`.
+    watchdog: Option<Arc<Watchdog>>,
+    /// Sequence numbers of the values sent with `dropout_seq`.
+    sequences: Arc<Sequences>,
+    /// Number of dropper threads consuming the queue.
+    workers: usize,
+    /// Number of dropper threads running.
+    running: AtomicUsize,
+    /// Number of `Close` messages sent and not yet received.
+    closing: AtomicUsize,
+    /// Number of threads waiting on `pending_changed`, and of wakers in `wakers`.
+    waiters: AtomicUsize,
+    lock: Mutex<()>,
+    pending_changed: Condvar,
+    /// Wakers of the tasks waiting for a change, like threads waiting on `pending_changed`.
+    wakers: Mutex<Vec<Waker>>,
+    watermarks: Option<Watermarks>,
+    /// Set when the high watermark has been reached and the low one not yet.
+    above_watermark: AtomicBool,
+    worker: Mutex<WorkerSlot>,
+    /// Wakers of the consumer tasks waiting for a message.
+    consumers: Mutex<Vec<Waker>>,
+    /// Set when a consumer task is waiting for a message.
+    consumer_idle: AtomicBool,
+    /// Payload of the panic which stopped a consumer task.
+    panicked: Mutex<Option<Box<dyn Any + Send>>>,
+    /// Set when a dropper thread or consumer task has been stopped by a panic.
+    crashed: AtomicBool,
+    /// Set when observers are registered, to time the drops.
+    observed: AtomicBool,
+    observers: Mutex<Vec<Observer>>,
+    /// Number of observed drops, to identify them.
+    sequence: AtomicU64,
+    pacer: Option<Pacer>,
+    counters: Counters,
+}
+
+/// The handles of the running dropper threads.
+#[derive(Default)]
+struct WorkerSlot {
+    handles: Vec<thread::JoinHandle<()>>,
+    detached: bool,
+    /// Set when the consumers are run by a spawner: they cannot be joined, only waited for,
+    /// except the threads of a `ThreadSpawner` which are in `handles`.
+    tasks: bool,
+}
+
+/// Configuration needed to (re)spawn a dropper thread.
+struct ThreadConfig {
+    name: String,
+    stack_size: Option<usize>,
+    priority: Option<ThreadPriority>,
+    affinity: Option<Affinity>,
+    panic_policy: PanicPolicy,
+}
+
+impl Shared {
+    /// When the next message can be handled under the rate limit and throttle, `None` if it can
+    /// be now.
+    fn throttled(&self) -> Option<Instant> {
+        self.pacer.as_ref()?.throttled()
+    }
+
+    fn worker(&self) -> MutexGuard<'_, WorkerSlot> {
+        self.worker.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wait for the dropper threads to finish, unless they have been detached.
+    ///
+    /// Returns the payload of the first panic if any.
+    fn join(&self) -> thread::Result<()> {
+        // A restarted dropper thread registers its replacement before exiting,
+        // so we have to loop.
+        let mut result = Ok(());
+        loop {
+            let thread_handle = self.worker().handles.pop();
+            match thread_handle {
+                Some(thread_handle) => result = result.and(thread_handle.join()),
+                None => break,
+            }
+        }
+        let worker = self.worker();
+        if worker.tasks && !worker.detached {
+            drop(worker);
+            self.wait_while(|shared| !shared.finished.load(Ordering::SeqCst), None);
+            if let Some(payload) = lock(&self.panicked).take() {
+                result = result.and(Err(payload));
+            }
+        }
+        result
+    }
+
+    /// Remember the waker of a consumer task, to wake it up when a message is sent.
+    fn register_consumer(&self, waker: &Waker) {
+        lock(&self.consumers).push(waker.clone());
+        self.consumer_idle.store(true, Ordering::SeqCst);
+    }
+
+    /// Wake up the consumer tasks waiting for a message, if any.
+    fn wake_consumers(&self) {
+        if self.consumer_idle.load(Ordering::SeqCst)
+            && self.consumer_idle.swap(false, Ordering::SeqCst)
+        {
+            let consumers = mem::take(&mut *lock(&self.consumers));
+            consumers.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    /// Let the dropper threads drop values again, waking them up.
+    fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            self.notify();
+            self.wake_consumers();
+        }
+    }
+
+    /// Wait while the dropper is paused, or while the system is busy with an idle probe.
+    fn wait_resumed(&self) {
+        while let Some(deferral) = self.deferral() {
+            match deferral {
+                Deferral::Paused(until) => {
+                    self.wait_while(|shared| shared.paused.load(Ordering::SeqCst), until)
+                }
+                Deferral::Busy(until) => self.wait_while(|shared| !shared.stopping(), Some(until)),
+            };
+        }
+    }
+
+    /// Why the next message must be deferred, `None` if it can be handled now.
+    fn deferral(&self) -> Option<Deferral> {
+        // The values queued before the end of the last deferral are dropped first.
+        if self.draining.load(Ordering::SeqCst) > 0 {
+            return None;
+        }
+        let paused = self.paused.load(Ordering::SeqCst);
+        let busy = match paused {
+            true => None,
+            false => self.busy(),
+        };
+        if !paused && busy.is_none() {
+            self.end_deferral();
+            return None;
+        }
+        let deadline = self.max_deferral.map(|max_deferral| {
+            *lock(&self.deferred_since).get_or_insert_with(Instant::now) + max_deferral
+        });
+        match (busy, deadline) {
+            // Deferred for too long: the values queued so far are dropped anyway, then a new
+            // deferral period starts.
+            (_, Some(deadline)) if deadline <= Instant::now() => {
+                if lock(&self.deferred_since).take().is_some() {
+                    self.draining
+                        .store(self.queued.load(Ordering::SeqCst), Ordering::SeqCst);
+                }
+                None
+            }
+            (None, deadline) => Some(Deferral::Paused(deadline)),
+            (Some(until), deadline) => Some(Deferral::Busy(
+                deadline.map_or(until, |deadline| until.min(deadline)),
+            )),
+        }
+    }
+
+    /// Start a new deferral period, once the values deferred so far have been dropped.
+    fn end_deferral(&self) {
+        if self.max_deferral.is_some() {
+            lock(&self.deferred_since).take();
+            self.draining.store(0, Ordering::SeqCst);
+        }
+    }
+
+    /// Count `count` values received, ending the drain of the values queued before the end of the
+    /// last deferral.
+    fn drained(&self, count: usize) {
+        if self.max_deferral.is_some() {
+            let _ = self
+                .draining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |draining| {
+                    (draining > 0).then(|| draining.saturating_sub(count))
+                });
+        }
+    }
+
+    /// When to check the idle probe again if the system is busy, `None` if values can be dropped.
+    fn busy(&self) -> Option<Instant> {
+        let probe = self.idle_probe.as_ref()?;
+        match self.stopping() || (probe.is_idle)() {
+            true => None,
+            false => Some(Instant::now() + probe.interval),
+        }
+    }
+
+    /// Returns `true` if the dropper is being shut down or released, values are then dropped
+    /// without waiting for the system to be idle.
+    fn stopping(&self) -> bool {
+        self.closing.load(Ordering::SeqCst) > 0 || self.released.load(Ordering::SeqCst)
+    }
+
+    /// Increment the pending count.
+    fn acquire(&self, count: usize) {
+        let pending = self.pending.fetch_add(count, Ordering::SeqCst) + count;
+        if let Some(watermarks) = &self.watermarks {
+            if pending >= watermarks.high && !self.above_watermark.swap(true, Ordering::SeqCst) {
+                if let Some(on_high) = &watermarks.on_high {
+                    on_high();
+                }
+            }
+        }
+    }
+
+    /// Decrement the pending count, waking up waiting threads if any.
+    fn release(&self, count: usize) {
+        let pending = self.pending.fetch_sub(count, Ordering::SeqCst) - count;
+        if let Some(watermarks) = &self.watermarks {
+            if pending <= watermarks.low && self.above_watermark.swap(false, Ordering::SeqCst) {
+                if let Some(on_low) = &watermarks.on_low {
+                    on_low();
+                }
+            }
+        }
+        self.notify();
+    }
+
+    fn notify(&self) {
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            {
+                let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+                self.pending_changed.notify_all();
+            }
+            let wakers = mem::take(&mut *lock(&self.wakers));
+            self.waiters.fetch_sub(wakers.len(), Ordering::SeqCst);
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    /// Returns `true` if the drops must be timed, for the observers or the stats.
+    fn timed(&self) -> bool {
+        self.observed.load(Ordering::Relaxed) || self.counters.timed()
+    }
+
+    /// Report a drop to the stats and the observers.
+    fn observe(&self, type_name: &'static str, count: usize, duration: Duration) {
+        self.counters.record(type_name, count, duration);
+        if !self.observed.load(Ordering::Relaxed) {
+            return;
+        }
+        let event = DropEvent {
+            id: self.sequence.fetch_add(1, Ordering::Relaxed),
+            type_name,
+            count,
+            duration,
+        };
+        let mut observers = lock(&self.observers);
+        observers.retain_mut(|observer| observer(&event));
+        if observers.is_empty() {
+            self.observed.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Asynchronous version of `wait_while`: ready once `ready` is true.
+    ///
+    /// `ready` is checked each time the pending count changes or the thread stops.
+    fn poll_until(&self, cx: &mut Context<'_>, ready: impl Fn(&Self) -> bool) -> Poll<()> {
+        if ready(self) {
+            return Poll::Ready(());
+        }
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        lock(&self.wakers).push(cx.waker().clone());
+        // Check again, a change may have happened before we were registered.
+        match ready(self) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+
+    /// Block until less than `threshold` values are pending or the dropper threads have stopped.
+    fn wait_until_below(&self, threshold: usize) {
+        let threshold = threshold.max(1);
+        self.wait_while(
+            |shared| {
+                shared.pending.load(Ordering::SeqCst) >= threshold
+                    && !shared.finished.load(Ordering::SeqCst)
+            },
+            None,
+        );
+    }
+
+    /// Block until the dropper threads have stopped or `deadline` is reached.
+    ///
+    /// Returns `false` if the deadline is reached.
+    fn wait_finished(&self, deadline: Instant) -> bool {
+        self.wait_while(
+            |shared| !shared.finished.load(Ordering::SeqCst),
+            Some(deadline),
+        )
+    }
+
+    /// Block while `condition` is true, at most until `deadline`.
+    ///
+    /// `condition` is checked each time the pending count changes or the thread stops.
+    /// Returns `false` if the deadline is reached.
+    fn wait_while(&self, condition: impl Fn(&Self) -> bool, deadline: Option<Instant>) -> bool {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut done = true;
+        while condition(self) {
+            guard = match deadline {
+                None => self
+                    .pending_changed
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        done = false;
+                        break;
+                    }
+                    self.pending_changed
+                        .wait_timeout(guard, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+        drop(guard);
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        done
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Mark the dropper thread as finished when dropped, even if the thread is panicking.
+struct FinishGuard<'a>(&'a Shared);
+
+impl Drop for FinishGuard<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.crashed.store(true, Ordering::SeqCst);
+        }
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.finished.store(true, Ordering::SeqCst);
+            // Nothing more to observe.
+            lock(&self.0.observers).clear();
+        }
+        // Flushing threads may wait for this one.
+        self.0.notify();
+    }
+}
+
+/// Release pending values when dropped, even if the thread is panicking.
+struct ReleaseGuard<'a>(&'a Shared, usize);
+
+impl Drop for ReleaseGuard<'_> {
+    fn drop(&mut self) {
+        let pending = self.0.pending.load(Ordering::SeqCst);
+        self.0
+            .counters
+            .dropped(self.1, pending.saturating_sub(self.1));
+        self.0.release(self.1);
+    }
+}
+
+/// Count the bytes of a message as dropped when dropped, even on panic.
+struct BytesGuard<'a>(&'a Shared, usize);
+
+impl Drop for BytesGuard<'_> {
+    fn drop(&mut self) {
+        if self.1 > 0 {
+            self.0.counters.dropped_bytes(self.1);
+        }
+    }
+}
+
+/// A closure run when dropped, to send jobs as type erased values.
+struct Job<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> Drop for Job<F> {
+    fn drop(&mut self) {
+        if let Some(f) = self.0.take() {
+            f();
+        }
+    }
+}
+
+/// Wrap `f` in a value running it when dropped, returned with the name of the type of `f`.
+pub fn job<F: FnOnce() + Send + 'static>(f: F) -> (Box<dyn Any + Send>, &'static str) {
+    (Box::new(Job(Some(f))), any::type_name::<F>())
+}
+
+/// Wrap `future` to be run to completion, returned with the name of its type.
+pub fn cleanup<F: Future<Output = ()> + Send + 'static>(future: F) -> (Cleanup, &'static str) {
+    (Cleanup(Some(Box::pin(future))), any::type_name::<F>())
+}
+
+/// The dropper behind an `AnyDropper`, whose thread can be shared with typed droppers.
+pub type Core = Dropper<Box<dyn Any + Send>>;
+
+/// The number of messages sent to a queue and received from it, to order the barriers.
+///
+/// A message is counted as sent before it is pushed, so that a barrier waits for the messages
+/// being pushed when it is sent. Those failing to be pushed are counted as abandoned.
+#[derive(Default)]
+struct Tally {
+    sent: AtomicU64,
+    abandoned: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Tally {
+    /// Number of messages sent to the queue which are received or abandoned.
+    fn done(&self) -> u64 {
+        self.received.load(Ordering::SeqCst) + self.abandoned.load(Ordering::SeqCst)
+    }
+}
+
+/// The kind of channel of the queues.
+#[derive(Clone, Copy)]
+enum Backend {
+    Crossbeam,
+    /// Tokio mpsc channels, only read by consumer tasks: a dropper thread waits on the
+    /// crossbeam channels.
+    #[cfg(feature = "tokio")]
+    Tokio,
+}
+
+impl Backend {
+    /// The backend configured by `config`, for consumer tasks if `tasks`.
+    #[cfg_attr(not(feature = "tokio"), allow(unused_variables))]
+    fn new(config: &DropperBuilder, tasks: bool) -> Self {
+        #[cfg(feature = "tokio")]
+        if config.tokio_channel && tasks {
+            return Self::Tokio;
+        }
+        Self::Crossbeam
+    }
+}
+
+/// The sending half of the channel of a queue.
+enum ChannelSender<T> {
+    Crossbeam(Sender<Message<T>>),
+    #[cfg(feature = "tokio")]
+    Tokio(mpsc::Sender<Message<T>>),
+}
+
+/// The receiving half of the channel of a queue.
+enum ChannelReceiver<T> {
+    Crossbeam(Receiver<Message<T>>),
+    /// Shared by the clones of the receiver, as a tokio receiver is not `Clone`.
+    #[cfg(feature = "tokio")]
+    Tokio(Arc<Mutex<mpsc::Receiver<Message<T>>>>),
+}
+
+/// The sender of a queue.
+struct QueueSender<T> {
+    sender: ChannelSender<T>,
+    tally: Arc<Tally>,
+}
+
+impl<T> QueueSender<T> {
+    fn send(
+        &self,
+        message: Message<T>,
+        mode: SendMode,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        self.tally.sent.fetch_add(1, Ordering::SeqCst);
+        let result = match &self.sender {
+            ChannelSender::Crossbeam(sender) => match mode {
+                SendMode::Block => sender
+                    .send(message)
+                    .map_err(|e| SendTimeoutError::Disconnected(e.into_inner())),
+                SendMode::Try => sender.try_send(message).map_err(|e| match e {
+                    TrySendError::Full(message) => SendTimeoutError::Timeout(message),
+                    TrySendError::Disconnected(message) => SendTimeoutError::Disconnected(message),
+                }),
+                SendMode::Deadline(deadline) => sender.send_deadline(message, deadline),
+            },
+            #[cfg(feature = "tokio")]
+            ChannelSender::Tokio(sender) => send_tokio(sender, message, mode),
+        };
+        if result.is_err() {
+            self.tally.abandoned.fetch_add(1, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// Number of free slots in the queue and its capacity, `None` if it is unbounded.
+    fn free_slots(&self) -> Option<(usize, usize)> {
+        match &self.sender {
+            ChannelSender::Crossbeam(sender) => {
+                let capacity = sender.capacity()?;
+                Some((capacity.saturating_sub(sender.len()), capacity))
+            }
+            #[cfg(feature = "tokio")]
+            ChannelSender::Tokio(sender) => match sender.max_capacity() {
+                Semaphore::MAX_PERMITS => None,
+                capacity => Some((sender.capacity(), capacity)),
+            },
+        }
+    }
+}
+
+/// Send a message to a tokio channel, waiting for room in the current thread as `mode` says.
+#[cfg(feature = "tokio")]
+fn send_tokio<T>(
+    sender: &mpsc::Sender<Message<T>>,
+    message: Message<T>,
+    mode: SendMode,
+) -> Result<(), SendTimeoutError<Message<T>>> {
+    // The room is reserved first, the message is not moved into the future.
+    let reserved = match mode {
+        SendMode::Block => {
+            block_on(sender.reserve()).map_err(|_| SendTimeoutError::Disconnected(()))
+        }
+        SendMode::Try => sender.try_reserve().map_err(|e| match e {
+            mpsc::error::TrySendError::Full(()) => SendTimeoutError::Timeout(()),
+            mpsc::error::TrySendError::Closed(()) => SendTimeoutError::Disconnected(()),
+        }),
+        SendMode::Deadline(deadline) => match block_on_until(sender.reserve(), deadline) {
+            Some(reserved) => reserved.map_err(|_| SendTimeoutError::Disconnected(())),
+            None => Err(SendTimeoutError::Timeout(())),
+        },
+    };
+    match reserved {
+        Ok(permit) => {
+            permit.send(message);
+            Ok(())
+        }
+        Err(SendTimeoutError::Timeout(())) => Err(SendTimeoutError::Timeout(message)),
+        Err(SendTimeoutError::Disconnected(())) => Err(SendTimeoutError::Disconnected(message)),
+    }
+}
+
+/// The receiver of a queue.
+struct QueueReceiver<T> {
+    receiver: ChannelReceiver<T>,
+    tally: Arc<Tally>,
+}
+
+impl<T> Clone for QueueReceiver<T> {
+    fn clone(&self) -> Self {
+        let receiver = match &self.receiver {
+            ChannelReceiver::Crossbeam(receiver) => ChannelReceiver::Crossbeam(receiver.clone()),
+            #[cfg(feature = "tokio")]
+            ChannelReceiver::Tokio(receiver) => ChannelReceiver::Tokio(Arc::clone(receiver)),
+        };
+        Self {
+            receiver,
+            tally: Arc::clone(&self.tally),
+        }
+    }
+}
+
+impl<T> QueueReceiver<T> {
+    fn is_empty(&self) -> bool {
+        match &self.receiver {
+            ChannelReceiver::Crossbeam(receiver) => receiver.is_empty(),
+            #[cfg(feature = "tokio")]
+            ChannelReceiver::Tokio(receiver) => lock(receiver).is_empty(),
+        }
+    }
+
+    fn try_recv(&self) -> Result<Message<T>, TryRecvError> {
+        let result = match &self.receiver {
+            ChannelReceiver::Crossbeam(receiver) => receiver.try_recv(),
+            #[cfg(feature = "tokio")]
+            ChannelReceiver::Tokio(receiver) => try_recv_tokio(receiver),
+        };
+        if result.is_ok() {
+            self.tally.received.fetch_add(1, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// The crossbeam receiver, to wait on it.
+    fn crossbeam(&self) -> Option<&Receiver<Message<T>>> {
+        match &self.receiver {
+            ChannelReceiver::Crossbeam(receiver) => Some(receiver),
+            #[cfg(feature = "tokio")]
+            ChannelReceiver::Tokio(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn try_recv_tokio<T>(
+    receiver: &Mutex<mpsc::Receiver<Message<T>>>,
+) -> Result<Message<T>, TryRecvError> {
+    lock(receiver).try_recv().map_err(|e| match e {
+        mpsc::error::TryRecvError::Empty => TryRecvError::Empty,
+        mpsc::error::TryRecvError::Disconnected => TryRecvError::Disconnected,
+    })
+}
+
+fn queue<T>(capacity: Option<usize>, backend: Backend) -> (QueueSender<T>, QueueReceiver<T>) {
+    let (sender, receiver) = match backend {
+        Backend::Crossbeam => {
+            let (sender, receiver) = match capacity {
+                Some(capacity) => bounded(capacity),
+                None => unbounded(),
+            };
+            (
+                ChannelSender::Crossbeam(sender),
+                ChannelReceiver::Crossbeam(receiver),
+            )
+        }
+        #[cfg(feature = "tokio")]
+        Backend::Tokio => {
+            // A tokio channel is never a rendezvous one, and its largest capacity is unbounded
+            // in practice (see `free_slots`).
+            let capacity = capacity.map_or(Semaphore::MAX_PERMITS, |capacity| capacity.max(1));
+            let (sender, receiver) = mpsc::channel(capacity);
+            (
+                ChannelSender::Tokio(sender),
+                ChannelReceiver::Tokio(Arc::new(Mutex::new(receiver))),
+            )
+        }
+    };
+    let tally = Arc::new(Tally::default());
+    (
+        QueueSender {
+            sender,
+            tally: Arc::clone(&tally),
+        },
+        QueueReceiver { receiver, tally },
+    )
+}
+
+/// How long a dropper thread waits before checking again if a pending barrier is reached.
+const BARRIER_POLL: Duration = Duration::from_millis(1);
+
+/// A `Flush` or `Close` message, handled after the messages sent before it to every queue.
+struct Barrier {
+    /// The marker of a `Flush` message, `None` for a `Close` one.
+    marker: Option<Arc<FlushMarker>>,
+    /// Number of dropper threads to receive the barrier: a flush marker is received by each of
+    /// them, sent at once so the markers of two flushes don't interleave.
+    copies: usize,
+    /// The queues with messages not yet received when the barrier was sent, and the number of
+    /// messages sent to them then.
+    targets: Vec<(Arc<Tally>, u64)>,
+}
+
+impl Barrier {
+    fn into_message<T>(self) -> Message<T> {
+        match self.marker {
+            Some(marker) => Message::Flush(marker),
+            None => Message::Close,
+        }
+    }
+
+    /// Take a copy of the barrier in `pending`, removing it once all its copies are taken.
+    fn take_copy(pending: &mut Option<Self>) -> Self {
+        let barrier = pending.as_mut().expect("A barrier is pending");
+        barrier.copies -= 1;
+        match barrier.copies {
+            0 => pending.take().unwrap(),
+            _ => Self {
+                marker: barrier.marker.clone(),
+                copies: 1,
+                targets: Vec::new(),
+            },
+        }
+    }
+
+    /// Returns `true` once the messages sent before the barrier are received.
+    fn is_reached(&self) -> bool {
+        self.targets
+            .iter()
+            .all(|(tally, sent)| tally.done() >= *sent)
+    }
+
+    /// Returns `true` if messages sent before the barrier are still in the queue of `tally`.
+    fn waits_for(&self, tally: &Arc<Tally>) -> bool {
+        self.targets
+            .iter()
+            .any(|(target, sent)| Arc::ptr_eq(target, tally) && target.done() < *sent)
+    }
+}
+
+/// What to receive next while a barrier is pending.
+enum Next<T> {
+    /// The barrier, all the messages sent before it are received.
+    Barrier(Barrier),
+    /// A queue with messages sent before the barrier.
+    Queue(QueueReceiver<T>),
+    /// The messages sent before the barrier are being received by other threads or pushed.
+    Waiting,
+}
+
+/// The senders of the queues of a dropper, one per priority.
+struct Senders<T> {
+    queues: [QueueSender<T>; 3],
+    /// `Flush` and `Close` messages, received once the messages sent before them to each queue
+    /// are, whatever their priority: steady traffic in a queue can't delay them.
+    barriers: Sender<Barrier>,
+    lanes: Arc<LaneSet<T>>,
+}
+
+impl<T> Senders<T> {
+    /// The sender of the queue of `priority`.
+    fn get(&self, priority: Priority) -> &QueueSender<T> {
+        &self.queues[priority as usize]
+    }
+
+    /// Send a barrier after the messages already sent to the queues and the lanes.
+    fn send_barrier(&self, message: Message<T>) -> Result<(), SendTimeoutError<Message<T>>> {
+        let (marker, copies) = match message {
+            Message::Flush(marker) => {
+                let copies = marker.sent;
+                (Some(marker), copies)
+            }
+            _ => (None, 1),
+        };
+        let mut targets = Vec::new();
+        let tallies = self.queues.iter().map(|queue| Arc::clone(&queue.tally));
+        for tally in tallies.chain(self.lanes.tallies()) {
+            let sent = tally.sent.load(Ordering::SeqCst);
+            if tally.done() < sent {
+                targets.push((tally, sent));
+            }
+        }
+        self.barriers
+            .send(Barrier {
+                marker,
+                copies,
+                targets,
+            })
+            .map_err(|e| SendTimeoutError::Disconnected(e.into_inner().into_message()))
+    }
+}
+
+/// The queues of the droppers attached with a weight (see `Dropper::attached_weighted`).
+///
+/// They are received at the `Normal` priority, in weighted round robin with the `Normal` queue:
+/// each queue gives up to its weight of messages in turn.
+struct LaneSet<T> {
+    /// Set once a lane has been added, to skip the lock before.
+    active: AtomicBool,
+    lanes: Mutex<Lanes<T>>,
+}
+
+struct Lanes<T> {
+    capacity: Option<usize>,
+    backend: Backend,
+    lanes: Vec<Lane<T>>,
+    /// Index of the queue being served: 0 for the `Normal` queue, `i + 1` for `lanes[i]`.
+    current: usize,
+    /// Number of messages the current queue can still give before the next one is served.
+    credit: usize,
+}
+
+struct Lane<T> {
+    receiver: QueueReceiver<T>,
+    weight: usize,
+}
+
+impl<T> Lanes<T> {
+    /// Serve the next queue.
+    fn next(&mut self) {
+        self.current = (self.current + 1) % (self.lanes.len() + 1);
+        self.credit = match self.current {
+            0 => 1,
+            index => self.lanes[index - 1].weight,
+        };
+    }
+}
+
+impl<T> LaneSet<T> {
+    fn new(capacity: Option<usize>, backend: Backend) -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            lanes: Mutex::new(Lanes {
+                capacity,
+                backend,
+                lanes: Vec::new(),
+                current: 0,
+                credit: 1,
+            }),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Add a lane of `weight`, returning its sender.
+    fn add(&self, weight: usize) -> QueueSender<T> {
+        let mut lanes = lock(&self.lanes);
+        let (sender, receiver) = queue(lanes.capacity, lanes.backend);
+        lanes.lanes.push(Lane { receiver, weight });
+        self.active.store(true, Ordering::Release);
+        sender
+    }
+
+    /// Receive the next message of the `normal` queue or the lanes, in weighted round robin.
+    fn try_recv(&self, normal: &QueueReceiver<T>) -> Result<Message<T>, TryRecvError> {
+        let mut lanes = lock(&self.lanes);
+        if lanes.credit == 0 {
+            lanes.next();
+        }
+        // Each queue is tried once.
+        for _ in 0..=lanes.lanes.len() {
+            let received = match lanes.current {
+                0 => normal.try_recv(),
+                index => lanes.lanes[index - 1].receiver.try_recv(),
+            };
+            match received {
+                Ok(message) => {
+                    lanes.credit -= 1;
+                    return Ok(message);
+                }
+                // The dropper of the lane is gone, and its values have been received.
+                Err(TryRecvError::Disconnected) if lanes.current > 0 => {
+                    let index = lanes.current - 1;
+                    lanes.lanes.remove(index);
+                    lanes.current = index;
+                }
+                Err(_) => {}
+            }
+            lanes.next();
+        }
+        normal.try_recv()
+    }
+
+    /// A lane with messages sent before `barrier`.
+    fn before(&self, barrier: &Barrier) -> Option<QueueReceiver<T>> {
+        if !self.is_active() {
+            return None;
+        }
+        lock(&self.lanes)
+            .lanes
+            .iter()
+            .find(|lane| barrier.waits_for(&lane.receiver.tally) && !lane.receiver.is_empty())
+            .map(|lane| lane.receiver.clone())
+    }
+
+    /// Take the messages of all the lanes into `stack`, removing the lanes whose dropper is gone.
+    fn drain_into(&self, stack: &mut Vec<Message<T>>) {
+        lock(&self.lanes).lanes.retain(|lane| loop {
+            match lane.receiver.try_recv() {
+                Ok(message) => stack.push(message),
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Disconnected) => break false,
+            }
+        });
+    }
+
+    /// The receivers of the lanes, to wait on them.
+    fn receivers(&self) -> Vec<Receiver<Message<T>>> {
+        match self.is_active() {
+            true => lock(&self.lanes)
+                .lanes
+                .iter()
+                .filter_map(|lane| lane.receiver.crossbeam().cloned())
+                .collect(),
+            false => Vec::new(),
+        }
+    }
+
+    /// The tallies of the lanes, to send a barrier after their messages.
+    fn tallies(&self) -> Vec<Arc<Tally>> {
+        match self.is_active() {
+            true => lock(&self.lanes)
+                .lanes
+                .iter()
+                .map(|lane| Arc::clone(&lane.receiver.tally))
+                .collect(),
+            false => Vec::new(),
+        }
+    }
+}
+
+/// The receivers of the queues of a dropper, the messages of higher priority are received first.
+struct Queues<T> {
+    receivers: [QueueReceiver<T>; 3],
+    barriers: Receiver<Barrier>,
+    /// The barrier received from `barriers`, handled once the messages sent before it are.
+    barrier: Mutex<Option<Barrier>>,
+    lanes: Arc<LaneSet<T>>,
+    /// With [`DropOrder::Lifo`], the messages taken from the queues, the newest on top.
+    stack: Option<Mutex<Vec<Message<T>>>>,
+}
+
+impl<T> Queues<T> {
+    fn new(capacity: Option<usize>, order: DropOrder, backend: Backend) -> (Senders<T>, Self) {
+        let [(high_sender, high), (normal_sender, normal), (low_sender, low)] =
+            [(); 3].map(|_| queue(capacity, backend));
+        let (barrier_sender, barriers) = unbounded();
+        let stack = match order {
+            DropOrder::Fifo => None,
+            DropOrder::Lifo => Some(Mutex::default()),
+        };
+        let lanes = Arc::new(LaneSet::new(capacity, backend));
+        (
+            Senders {
+                queues: [high_sender, normal_sender, low_sender],
+                barriers: barrier_sender,
+                lanes: Arc::clone(&lanes),
+            },
+            Self {
+                receivers: [high, normal, low],
+                barriers,
+                barrier: Mutex::default(),
+                lanes,
+                stack,
+            },
+        )
+    }
+
+    /// Receive the next message of the highest priority, without waiting.
+    fn try_recv(&self) -> Result<Message<T>, TryRecvError> {
+        let Some(stack) = &self.stack else {
+            return self.try_recv_first();
+        };
+        let mut stack = lock(stack);
+        if let Some(message) = stack.pop() {
+            return Ok(message);
+        }
+        self.fill(&mut stack)?;
+        Ok(stack.pop().expect("The stack was just filled"))
+    }
+
+    /// The pending barrier, received from `barriers` if there is none.
+    fn barrier(&self) -> MutexGuard<'_, Option<Barrier>> {
+        let mut barrier = lock(&self.barrier);
+        if barrier.is_none() {
+            *barrier = self.barriers.try_recv().ok();
+        }
+        barrier
+    }
+
+    /// Receive the oldest message of the highest priority.
+    ///
+    /// While a barrier is pending, the messages sent before it are received first, whatever
+    /// their priority, then the barrier.
+    fn try_recv_first(&self) -> Result<Message<T>, TryRecvError> {
+        if !self.barriers.is_empty() || lock(&self.barrier).is_some() {
+            match self.next_before_barrier() {
+                Next::Barrier(barrier) => return Ok(barrier.into_message()),
+                Next::Queue(queue) => {
+                    let received = queue.try_recv();
+                    if received.is_ok() {
+                        return received;
+                    }
+                }
+                // The messages sent before the barrier are still being pushed.
+                Next::Waiting => {}
+            }
+        }
+        let mut error = TryRecvError::Disconnected;
+        for (priority, receiver) in self.receivers.iter().enumerate() {
+            let received = match priority == Priority::Normal as usize && self.lanes.is_active() {
+                true => self.lanes.try_recv(receiver),
+                false => receiver.try_recv(),
+            };
+            match received {
+                Ok(message) => return Ok(message),
+                Err(TryRecvError::Empty) => error = TryRecvError::Empty,
+                Err(TryRecvError::Disconnected) => {}
+            }
+        }
+        Err(error)
+    }
+
+    /// The pending barrier if it is reached, else the queue of the next message sent before it.
+    ///
+    /// Only the queue is returned, to receive the message in `try_recv_first`: messages may be
+    /// large, and each frame they go through takes room on the stack.
+    fn next_before_barrier(&self) -> Next<T> {
+        let mut barrier = self.barrier();
+        let Some(pending) = barrier.as_ref() else {
+            return Next::Waiting;
+        };
+        if pending.is_reached() {
+            return Next::Barrier(Barrier::take_copy(&mut barrier));
+        }
+        for (priority, receiver) in self.receivers.iter().enumerate() {
+            if priority == Priority::Normal as usize {
+                if let Some(lane) = self.lanes.before(pending) {
+                    return Next::Queue(lane);
+                }
+            }
+            if pending.waits_for(&receiver.tally) && !receiver.is_empty() {
+                return Next::Queue(receiver.clone());
+            }
+        }
+        Next::Waiting
+    }
+
+    /// Take the queued messages into `stack`, so that they are popped by priority, the newest
+    /// first.
+    ///
+    /// A pending barrier is put below the values once those sent before it are taken.
+    fn fill(&self, stack: &mut Vec<Message<T>>) -> Result<(), TryRecvError> {
+        let mut barrier = self.barrier();
+        let mut result = Err(TryRecvError::Disconnected);
+        for (priority, receiver) in self.receivers.iter().enumerate().rev() {
+            if priority == Priority::Normal as usize && self.lanes.is_active() {
+                self.lanes.drain_into(stack);
+            }
+            loop {
+                match receiver.try_recv() {
+                    Ok(message) => stack.push(message),
+                    Err(TryRecvError::Empty) => {
+                        result = Err(TryRecvError::Empty);
+                        break;
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            }
+        }
+        if barrier.as_ref().is_some_and(Barrier::is_reached) {
+            stack.insert(0, Barrier::take_copy(&mut barrier).into_message());
+        }
+        match stack.is_empty() {
+            true => result,
+            false => Ok(()),
+        }
+    }
+
+    /// Wait for the next message of the highest priority.
+    fn recv(&self) -> Result<Message<T>, RecvError> {
+        loop {
+            match self.try_recv() {
+                Ok(message) => return Ok(message),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => {
+                    let lanes = self.lanes.receivers();
+                    let mut select = Select::new();
+                    for receiver in self.receivers.iter().filter_map(QueueReceiver::crossbeam) {
+                        select.recv(receiver);
+                    }
+                    for receiver in &lanes {
+                        select.recv(receiver);
+                    }
+                    select.recv(&self.barriers);
+                    // Another worker may take the message first, so we only wait here.
+                    match lock(&self.barrier).is_some() {
+                        // The messages before the barrier may be abandoned by their senders.
+                        true => drop(select.ready_timeout(BARRIER_POLL)),
+                        false => drop(select.ready()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where a dropper sends its messages.
+enum Sink<T> {
+    /// The queues of the dropper's own thread.
+    Owned(Senders<T>),
+    /// The dropper owning a thread shared with other droppers, and the lane of this dropper if
+    /// attached with a weight. Values are boxed.
+    Attached(Arc<Core>, Option<QueueSender<Box<dyn Any + Send>>>),
+}
+
+/// How to wait for room in the queue.
+#[derive(Clone, Copy)]
+enum SendMode {
+    Block,
+    Try,
+    Deadline(Instant),
+}
+
+pub struct Dropper<T: Send> {
+    /// Shared with the `WeakSender`s, which don't own the dropper threads.
+    sink: Option<Arc<Sink<T>>>,
+    overflow_policy: OverflowPolicy,
+    shared: Arc<Shared>,
+}
+
+impl<T: Send + 'static> Dropper<T> {
+    pub fn new(config: DropperBuilder) -> io::Result<Self> {
+        Self::build(config, None)
+    }
+
+    /// Create a dropper whose consumers are run by `spawn` instead of dedicated threads.
+    pub fn with_spawn(
+        config: DropperBuilder,
+        mut spawn: impl FnMut(Consumer) -> io::Result<()>,
+    ) -> io::Result<Self> {
+        Self::build(config, Some(&mut spawn))
+    }
+
+    fn build(
+        config: DropperBuilder,
+        spawn: Option<&mut dyn FnMut(Consumer) -> io::Result<()>>,
+    ) -> io::Result<Self> {
+        let backend = Backend::new(&config, spawn.is_some());
+        let (drop_sender, drop_receiver) = Queues::new(config.capacity, config.order, backend);
+        let pacer = Pacer::new(&config);
+        let counters = Counters::new(&config);
+        let shared = Arc::new(Shared {
+            watermarks: config.watermarks,
+            workers: config.workers,
+            running: AtomicUsize::new(config.workers),
+            pacer,
+            idle_probe: config.idle_probe,
+            max_deferral: config.max_deferral,
+            watchdog: config.watchdog.map(Watchdog::start).transpose()?,
+            counters,
+            ..Default::default()
+        });
+        let thread_config = Arc::new(ThreadConfig {
+            name: config.name,
+            stack_size: config.stack_size,
+            priority: config.priority,
+            affinity: config.affinity,
+            panic_policy: config.panic_policy,
+        });
+        match spawn {
+            None => {
+                let yield_every = config.yield_every;
+                let runner: Runner =
+                    Arc::new(move |shared| run(&drop_receiver, shared, yield_every));
+                for _ in 0..config.workers {
+                    let thread_handle = spawn_worker(
+                        Arc::clone(&runner),
+                        Arc::clone(&shared),
+                        Arc::clone(&thread_config),
+                    )?;
+                    shared.worker().handles.push(thread_handle);
+                }
+            }
+            Some(spawn) => {
+                shared.worker().tasks = true;
+                let stepper: Stepper =
+                    Arc::new(move |shared, ongoing| step(&drop_receiver, shared, ongoing));
+                for _ in 0..config.workers {
+                    spawn(Consumer {
+                        stepper: Arc::clone(&stepper),
+                        shared: Arc::clone(&shared),
+                        ongoing: Ongoing::polling(Arc::clone(&shared)),
+                        panic_policy: config.panic_policy,
+                        budget: config.task_budget,
+                        flushing: None,
+                        closed: false,
+                    })?;
+                }
+            }
+        }
+        Ok(Self {
+            sink: Some(Arc::new(Sink::Owned(drop_sender))),
+            overflow_policy: config.overflow_policy,
+            shared,
+        })
+    }
+
+    /// Create a dropper sending its values to the thread of `owner`, through a lane of `weight`.
+    pub fn attached_weighted(owner: &Arc<Core>, weight: usize) -> Self {
+        Self {
+            sink: Some(Arc::new(Sink::Attached(
+                Arc::clone(owner),
+                owner.add_lane(weight),
+            ))),
+            overflow_policy: owner.overflow_policy,
+            shared: Arc::clone(&owner.shared),
+        }
+    }
+
+    /// Create a dropper sending its values to the thread of `owner`.
+    pub fn attached(owner: &Arc<Core>) -> Self {
+        Self {
+            sink: Some(Arc::new(Sink::Attached(Arc::clone(owner), None))),
+            overflow_policy: owner.overflow_policy,
+            shared: Arc::clone(&owner.shared),
+        }
+    }
+
+    /// Send the object to be drop.
+    ///
+    /// If somehow the receiving part is closed (probably because of a panic in a previous object drop),
+    /// `to_drop` will be drop in the current thread.
+    /// If the queue is full, the overflow policy is applied.
+    #[inline]
+    pub fn dropout(&self, to_drop: T) {
+        self.dropout_message(Message::Drop(to_drop))
+    }
+
+    /// Send a message, applying the overflow policy if the queue is full.
+    #[inline]
+    fn dropout_message(&self, message: Message<T>) {
+        self.dropout_message_with(message, Priority::Normal)
+    }
+
+    /// Send a message with `priority`, applying the overflow policy if its queue is full.
+    #[inline]
+    fn dropout_message_with(&self, message: Message<T>, priority: Priority) {
+        match self.overflow_policy {
+            OverflowPolicy::Block => {
+                self.drop_unsent(self.send_with(message, SendMode::Block, priority));
+            }
+            OverflowPolicy::DropInline => {
+                self.drop_unsent(self.send_with(message, SendMode::Try, priority));
+            }
+            OverflowPolicy::Reject => match self.send_with(message, SendMode::Try, priority) {
+                Err(SendTimeoutError::Timeout(message)) => {
+                    self.shared.counters.rejected(message.count());
+                }
+                result => self.drop_unsent(result),
+            },
+        }
+    }
+
+    /// Drop the values of a message which could not be sent in the current thread.
+    fn drop_unsent(&self, result: Result<(), SendTimeoutError<Message<T>>>) {
+        if let Err(e) = result {
+            let count = e.into_inner().count();
+            self.shared.counters.dropped_inline(count);
+        }
+    }
+
+    /// Send the object to be drop, before (or after) the values of lower (or higher) priority.
+    #[inline]
+    pub fn dropout_with_priority(&self, to_drop: T, priority: Priority) {
+        self.dropout_message_with(Message::Drop(to_drop), priority)
+    }
+
+    /// Send the object to be drop, `bytes` being its size for the rate limit.
+    #[inline]
+    pub fn dropout_sized(&self, to_drop: T, bytes: usize) {
+        self.dropout_message(Message::Sized(Box::new(Message::Drop(to_drop)), bytes))
+    }
+
+    /// Try to send the object to be drop, giving it back if it cannot be sent.
+    #[inline]
+    pub fn try_dropout(&self, to_drop: T) -> Result<(), T> {
+        self.send(Message::Drop(to_drop), SendMode::Try)
+            .map_err(|e| e.into_inner().into_value())
+    }
+
+    /// Send the object to be drop, waiting for room in the queue.
+    ///
+    /// If somehow the receiving part is closed, `to_drop` will be drop in the current thread.
+    #[inline]
+    pub fn dropout_blocking(&self, to_drop: T) {
+        self.drop_unsent(self.send(Message::Drop(to_drop), SendMode::Block));
+    }
+
+    /// Send the object to be drop, asynchronously waiting for room in the queue.
+    ///
+    /// If somehow the receiving part is closed, `to_drop` will be drop in the current thread.
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub async fn dropout_async(&self, to_drop: T) {
+        self.drop_unsent(self.send_async(Message::Drop(to_drop)).await);
+    }
+
+    /// Send the object to be drop, or give it back if the queue is full, the task of `cx` being
+    /// woken up once there may be room.
+    ///
+    /// If somehow the receiving part is closed, `to_drop` will be drop in the current thread.
+    #[cfg(feature = "futures")]
+    pub fn poll_dropout(&self, cx: &mut Context<'_>, mut to_drop: T) -> Result<(), T> {
+        loop {
+            let received = self.shared.received.load(Ordering::SeqCst);
+            to_drop = match self.send(Message::Drop(to_drop), SendMode::Try) {
+                // Once the dropper threads have stopped, there won't be room anymore.
+                Err(SendTimeoutError::Timeout(message))
+                    if !self.shared.finished.load(Ordering::SeqCst) =>
+                {
+                    message.into_value()
+                }
+                result => {
+                    self.drop_unsent(result);
+                    return Ok(());
+                }
+            };
+            // The queue is full: wait for a message to be received, as `send_async`.
+            let room = self.shared.poll_until(cx, |shared| {
+                shared.received.load(Ordering::SeqCst) != received
+                    || shared.finished.load(Ordering::SeqCst)
+            });
+            if room.is_pending() {
+                return Err(to_drop);
+            }
+        }
+    }
+
+    /// Send the object to be drop, waiting at most `timeout` for room in the queue.
+    #[inline]
+    pub fn dropout_timeout(&self, to_drop: T, timeout: Duration) -> Result<(), T> {
+        let deadline = Instant::now() + timeout;
+        self.send(Message::Drop(to_drop), SendMode::Deadline(deadline))
+            .map_err(|e| e.into_inner().into_value())
+    }
+
+    /// Send all the values at once.
+    #[inline]
+    pub fn dropout_iter(&self, to_drop: impl IntoIterator<Item = T>) {
+        self.dropout_many(to_drop.into_iter().collect())
+    }
+
+    /// Send all the values at once, in one message.
+    #[inline]
+    pub fn dropout_many(&self, to_drop: Vec<T>) {
+        if !to_drop.is_empty() {
+            self.dropout_message(Message::Batch(to_drop))
+        }
+    }
+
+    /// Send a value to be dropped, boxed to not move it through the channel.
+    #[inline]
+    pub fn dropout_boxed(&self, to_drop: T) {
+        self.dropout_message(Message::Erased(Box::new(to_drop), any::type_name::<T>()))
+    }
+
+    /// Send a collection to be dropped `chunk_size` items at a time.
+    #[inline]
+    pub fn dropout_chunked<I>(&self, to_drop: I, chunk_size: usize)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+    {
+        self.dropout_message(Message::Chunked(Box::new(Chunks {
+            items: to_drop.into_iter(),
+            size: chunk_size.max(1),
+        })))
+    }
+
+    /// Send a closure to be run in the dropper thread, as if it was a value to drop.
+    #[inline]
+    pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+        let (job, type_name) = job(f);
+        self.dropout_message(Message::Erased(job, type_name))
+    }
+
+    /// Send a future to be run to completion.
+    pub fn defer_async(&self, cleanup: impl Future<Output = ()> + Send + 'static) {
+        let (cleanup, type_name) = self::cleanup(cleanup);
+        self.dropout_message(Message::Cleanup(cleanup, type_name))
+    }
+
+    /// Send the object to be drop, returning its sequence number.
+    pub fn dropout_seq(&self, to_drop: T) -> u64 {
+        let (sequence, guard) = self.shared.sequences.next();
+        self.defer(move || {
+            let _guard = guard;
+            drop(to_drop);
+        });
+        sequence
+    }
+
+    /// Wait for the values sent with `dropout_seq` up to `sequence` to be dropped.
+    pub fn wait_for(&self, sequence: u64) {
+        self.shared.sequences.wait_for(sequence)
+    }
+
+    /// Wait for all the messages sent before to be handled.
+    pub fn flush(&self) {
+        if let Ok(ack_receiver) = self.send_flush(SendMode::Block) {
+            // If the threads are gone, the ack sender is dropped and we return.
+            let _ = ack_receiver.recv();
+        }
+    }
+
+    /// Asynchronously wait for all the messages sent before to be handled.
+    pub async fn flush_async(&self) {
+        let (marker, ack_receiver) = self.flush_marker();
+        if self.send_async(Message::Flush(marker)).await.is_ok() {
+            // If the threads are gone, the ack sender is dropped (or will never be used).
+            future::poll_fn(|cx| {
+                self.shared.poll_until(cx, |shared| {
+                    !matches!(ack_receiver.try_recv(), Err(TryRecvError::Empty))
+                        || shared.finished.load(Ordering::SeqCst)
+                })
+            })
+            .await
+        }
+    }
+
+    /// Wait at most `timeout` for all the messages sent before to be handled.
+    ///
+    /// Returns `false` if the timeout expired.
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        match self.send_flush(SendMode::Deadline(deadline)) {
+            Ok(ack_receiver) => !matches!(
+                ack_receiver.recv_deadline(deadline),
+                Err(RecvTimeoutError::Timeout)
+            ),
+            Err(SendTimeoutError::Timeout(_)) => false,
+            Err(SendTimeoutError::Disconnected(_)) => true,
+        }
+    }
+
+    /// Create a flush marker for the dropper threads, and the receiver of its acknowledgement.
+    fn flush_marker(&self) -> (Arc<FlushMarker>, Receiver<()>) {
+        let workers = self.shared.workers;
+        let (ack_sender, ack_receiver) = bounded(workers);
+        let marker = Arc::new(FlushMarker {
+            ack: ack_sender,
+            arrived: AtomicUsize::new(0),
+            sent: workers,
+        });
+        (marker, ack_receiver)
+    }
+
+    /// Send a flush marker to each dropper thread, returning the receiver of the acknowledgement.
+    fn send_flush(&self, mode: SendMode) -> Result<Receiver<()>, SendTimeoutError<()>> {
+        let (marker, ack_receiver) = self.flush_marker();
+        match self.send(Message::Flush(marker), mode) {
+            Ok(()) => Ok(ack_receiver),
+            Err(SendTimeoutError::Timeout(_)) => Err(SendTimeoutError::Timeout(())),
+            Err(SendTimeoutError::Disconnected(_)) => Err(SendTimeoutError::Disconnected(())),
+        }
+    }
+
+    /// Register an observer of the drops.
+    pub fn observe(&self, observer: Observer) {
+        let shared = &self.shared;
+        lock(&shared.observers).push(observer);
+        shared.observed.store(true, Ordering::Relaxed);
+        if shared.finished.load(Ordering::SeqCst) {
+            lock(&shared.observers).clear();
+        }
+    }
+
+    /// Get a future completing once all the dropper threads have stopped.
+    pub fn closed(&self) -> Closed {
+        Closed(Arc::clone(&self.shared))
+    }
+
+    /// Returns `true` if a dropper thread is running.
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        !self.shared.finished.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the current thread is one of the dropper threads.
+    ///
+    /// A dropper thread must not wait for its own queue: it would wait for itself.
+    pub fn is_current_thread(&self) -> bool {
+        CURRENT_DROPPER.with(|current| ptr::eq(current.get(), Arc::as_ptr(&self.shared)))
+    }
+
+    /// Number of dropper threads consuming the queue.
+    #[inline]
+    pub fn workers(&self) -> usize {
+        self.shared.workers
+    }
+
+    /// Number of values waiting in the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shared.queued.load(Ordering::Relaxed)
+    }
+
+    /// Number of values not yet dropped.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.shared.pending.load(Ordering::SeqCst)
+    }
+
+    /// A snapshot of the activity of the dropper threads.
+    pub fn stats(&self) -> DropperStats {
+        self.shared.counters.snapshot(self.pending())
+    }
+
+    /// A handle on the stats of the dropper, which doesn't keep it open.
+    #[cfg(feature = "prometheus")]
+    pub fn stats_source(&self) -> StatsSource {
+        StatsSource(Arc::clone(&self.shared))
+    }
+
+    /// Block until less than `threshold` values are pending.
+    pub fn wait_until_below(&self, threshold: usize) {
+        self.shared.wait_until_below(threshold)
+    }
+
+    /// Asynchronously wait until the queue has `slots` free slots, or at most its capacity.
+    ///
+    /// Returns at once if the queue is unbounded.
+    pub async fn wait_for_capacity(&self, slots: usize) {
+        future::poll_fn(|cx| {
+            self.shared.poll_until(cx, |shared| {
+                self.free_slots()
+                    .is_none_or(|(free, capacity)| free >= slots.min(capacity))
+                    || shared.finished.load(Ordering::SeqCst)
+            })
+        })
+        .await
+    }
+
+    /// Number of free slots in the queue and its capacity, `None` if it is unbounded.
+    fn free_slots(&self) -> Option<(usize, usize)> {
+        match self.sink.as_deref()? {
+            Sink::Owned(senders) => senders.get(Priority::Normal).free_slots(),
+            Sink::Attached(owner, _) => owner.free_slots(),
+        }
+    }
+
+    /// Add a lane of `weight` to the queues, `None` if the dropper thread is shared.
+    fn add_lane(&self, weight: usize) -> Option<QueueSender<T>> {
+        let Some(Sink::Owned(senders)) = self.sink.as_deref() else {
+            return None;
+        };
+        let lane = senders.lanes.add(weight);
+        // The dropper threads waiting for a message don't wait on the new lane yet.
+        for _ in 0..self.shared.workers {
+            let _ = senders
+                .get(Priority::Normal)
+                .send(Message::Wake, SendMode::Try);
+        }
+        Some(lane)
+    }
+
+    /// Returns `true` if the dropper thread is shared with other droppers.
+    #[inline]
+    fn is_attached(&self) -> bool {
+        matches!(self.sink.as_deref(), Some(Sink::Attached(..)))
+    }
+
+    /// Stop the dropper thread once all values already sent are dropped, and wait for it.
+    ///
+    /// Values sent after that are dropped in the sending thread.
+    pub fn shutdown(&self) -> Result<(), ShutdownError> {
+        self.close().map_err(|_| ShutdownError::WorkerPanicked)
+    }
+
+    /// Same as `shutdown` but give the panic payload of the dropper thread.
+    ///
+    /// A shared thread is not stopped, we only wait for the values already sent.
+    pub fn close(&self) -> thread::Result<()> {
+        if self.is_attached() {
+            self.flush();
+            return Ok(());
+        }
+        self.send_close(SendMode::Block);
+        self.shared.join()
+    }
+
+    /// Same as `shutdown` but detach the dropper thread if it doesn't stop before `timeout`.
+    pub fn shutdown_timeout(&self, timeout: Duration) -> ShutdownOutcome {
+        if self.is_attached() {
+            return match self.flush_timeout(timeout) {
+                true => ShutdownOutcome::Completed,
+                false => ShutdownOutcome::TimedOut,
+            };
+        }
+        let deadline = Instant::now() + timeout;
+        if !self.send_close(SendMode::Deadline(deadline)) {
+            self.detach();
+            return ShutdownOutcome::TimedOut;
+        }
+        if !self.shared.wait_finished(deadline) {
+            self.detach();
+            return ShutdownOutcome::TimedOut;
+        }
+        match self.shared.join() {
+            Ok(()) => ShutdownOutcome::Completed,
+            Err(_) => ShutdownOutcome::WorkerPanicked,
+        }
+    }
+
+    /// Asynchronously stop the dropper threads and wait for them to finish.
+    ///
+    /// A shared thread is not stopped, we only wait for the values already sent.
+    #[cfg_attr(not(feature = "tokio-util"), allow(dead_code))]
+    pub async fn shutdown_async(&self) -> Result<(), ShutdownError> {
+        if self.is_attached() {
+            self.flush_async().await;
+            return Ok(());
+        }
+        let workers = self.shared.workers;
+        self.shared.closing.fetch_add(workers, Ordering::SeqCst);
+        self.shared.resume();
+        for _ in 0..workers {
+            let _ = self.send_async(Message::Close).await;
+        }
+        self.closed().await
+    }
+
+    /// Send a `Close` message to each dropper thread.
+    ///
+    /// Returns `false` if the deadline was reached.
+    fn send_close(&self, mode: SendMode) -> bool {
+        let workers = self.shared.workers;
+        self.shared.closing.fetch_add(workers, Ordering::SeqCst);
+        self.shared.resume();
+        (0..workers).all(|_| {
+            !matches!(
+                self.send(Message::Close, mode),
+                Err(SendTimeoutError::Timeout(_))
+            )
+        })
+    }
+
+    /// Stop dropping values until `resume` is called. Values can still be sent.
+    pub fn pause(&self) {
+        self.shared.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Drop the values again after a `pause`.
+    pub fn resume(&self) {
+        self.shared.resume();
+    }
+
+    /// Returns `true` if the dropper is paused.
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::SeqCst)
+    }
+
+    /// A sender of values to this dropper which doesn't own it, see `WeakSender`.
+    pub fn weak_sender(&self) -> WeakSender<T> {
+        WeakSender {
+            sink: self.sink.as_ref().map_or_else(Weak::new, Arc::downgrade),
+            overflow_policy: self.overflow_policy,
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+
+    /// Detach the dropper threads: nobody will wait for them to finish.
+    pub fn detach(&self) {
+        let mut worker = self.shared.worker();
+        worker.detached = true;
+        worker.handles.clear();
+    }
+
+    /// Send a message, asynchronously waiting for room in the queue.
+    async fn send_async(
+        &self,
+        mut message: Message<T>,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        #[cfg(feature = "tokio")]
+        if let Some(Sink::Owned(senders)) = self.sink.as_deref() {
+            let queue = senders.get(Priority::Normal);
+            if let ChannelSender::Tokio(sender) = &queue.sender {
+                if !message.is_barrier() {
+                    // Wait for room as a tokio sender does, then push the message with the
+                    // counters.
+                    return match sender.reserve().await {
+                        Ok(permit) => self.push(message, |message| {
+                            queue.tally.sent.fetch_add(1, Ordering::SeqCst);
+                            permit.send(message);
+                            Ok(())
+                        }),
+                        Err(_) => self.send(message, SendMode::Try),
+                    };
+                }
+            }
+        }
+        loop {
+            let received = self.shared.received.load(Ordering::SeqCst);
+            message = match self.send(message, SendMode::Try) {
+                Err(SendTimeoutError::Timeout(message)) => message,
+                result => return result,
+            };
+            // The queue is full: wait for a message to be received, the dropper threads notify
+            // the waiters once they have handled it.
+            future::poll_fn(|cx| {
+                self.shared.poll_until(cx, |shared| {
+                    shared.received.load(Ordering::SeqCst) != received
+                        || shared.finished.load(Ordering::SeqCst)
+                })
+            })
+            .await
+        }
+    }
+
+    /// Send a message with the normal priority, keeping the counters up to date.
+    ///
+    /// A full queue is reported as `Timeout`, whatever the mode.
+    #[inline]
+    fn send(
+        &self,
+        message: Message<T>,
+        mode: SendMode,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        self.send_with(message, mode, Priority::Normal)
+    }
+
+    /// Send a message to the queue of `priority`. See `send`.
+    fn send_with(
+        &self,
+        message: Message<T>,
+        mode: SendMode,
+        priority: Priority,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        match self.sink.as_deref().unwrap() {
+            Sink::Owned(senders) if message.is_barrier() => {
+                let result = senders.send_barrier(message);
+                self.shared.wake_consumers();
+                result
+            }
+            Sink::Owned(senders) => self.send_to(senders.get(priority), message, mode),
+            Sink::Attached(owner, lane) => {
+                let is_drop = matches!(message, Message::Drop(_));
+                let message = message.boxed();
+                let result = match lane {
+                    Some(lane) if priority == Priority::Normal && !message.is_barrier() => {
+                        owner.send_to(lane, message, mode)
+                    }
+                    _ => owner.send_with(message, mode, priority),
+                };
+                result.map_err(|e| match e {
+                    SendTimeoutError::Timeout(message) => {
+                        SendTimeoutError::Timeout(Message::unboxed(message, is_drop))
+                    }
+                    SendTimeoutError::Disconnected(message) => {
+                        SendTimeoutError::Disconnected(Message::unboxed(message, is_drop))
+                    }
+                })
+            }
+        }
+    }
+
+    /// Send a message to the queue of `sender`.
+    fn send_to(
+        &self,
+        sender: &QueueSender<T>,
+        message: Message<T>,
+        mode: SendMode,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        self.push(message, |message| sender.send(message, mode))
+    }
+
+    /// Push a message to a queue with `push`, keeping the counters up to date.
+    fn push(
+        &self,
+        message: Message<T>,
+        push: impl FnOnce(Message<T>) -> Result<(), SendTimeoutError<Message<T>>>,
+    ) -> Result<(), SendTimeoutError<Message<T>>> {
+        let count = message.count();
+        let bytes = match &message {
+            Message::Sized(_, bytes) => *bytes,
+            _ => 0,
+        };
+        if count > 0 {
+            self.shared.acquire(count);
+            self.shared.queued.fetch_add(count, Ordering::Relaxed);
+        }
+        let result = push(message);
+        match result {
+            Ok(()) => {
+                // Values failing to be sent are not counted in the peak.
+                let pending = self.shared.pending.load(Ordering::SeqCst);
+                self.shared.counters.sent(count, bytes, pending);
+                self.shared.wake_consumers();
+            }
+            Err(_) => {
+                if count > 0 {
+                    self.shared.queued.fetch_sub(count, Ordering::Relaxed);
+                    self.shared.release(count);
+                }
+                // A barrier may wait for the message.
+                self.shared.wake_consumers();
+            }
+        }
+        result
+    }
+}
+
+impl<T: Send + 'static> ErasedDropper for Dropper<T> {
+    fn dropout_erased(&self, to_drop: Box<dyn Any + Send>, type_name: &'static str) {
+        self.dropout_message(Message::Erased(to_drop, type_name))
+    }
+
+    fn defer_cleanup(&self, cleanup: Cleanup, type_name: &'static str) {
+        self.dropout_message(Message::Cleanup(cleanup, type_name))
+    }
+
+    fn try_dropout_erased(
+        &self,
+        to_drop: Box<dyn Any + Send>,
+        type_name: &'static str,
+    ) -> Result<(), Box<dyn Any + Send>> {
+        self.send(Message::Erased(to_drop, type_name), SendMode::Try)
+            .map_err(|e| e.into_inner().into_erased())
+    }
+
+    fn flush(&self) {
+        Dropper::flush(self)
+    }
+
+    fn flush_timeout(&self, timeout: Duration) -> bool {
+        Dropper::flush_timeout(self, timeout)
+    }
+
+    fn pending(&self) -> usize {
+        Dropper::pending(self)
+    }
+
+    fn stats(&self) -> DropperStats {
+        Dropper::stats(self)
+    }
+
+    fn is_healthy(&self) -> bool {
+        Dropper::is_healthy(self)
+    }
+
+    fn shutdown(&self) -> Result<(), ShutdownError> {
+        Dropper::shutdown(self)
+    }
+}
+
+fn spawn_worker(
+    runner: Runner,
+    shared: Arc<Shared>,
+    config: Arc<ThreadConfig>,
+) -> io::Result<thread::JoinHandle<()>> {
+    let mut thread_builder = thread::Builder::new().name(config.name.clone());
+    if let Some(stack_size) = config.stack_size {
+        thread_builder = thread_builder.stack_size(stack_size);
+    }
+    thread_builder.spawn(move || worker(runner, shared, config))
+}
+
+thread_local! {
+    /// The shared state of the dropper running in the current thread, null in other threads.
+    static CURRENT_DROPPER: Cell<*const Shared> = const { Cell::new(ptr::null()) };
+}
+
+fn worker(runner: Runner, shared: Arc<Shared>, config: Arc<ThreadConfig>) {
+    CURRENT_DROPPER.with(|current| current.set(Arc::as_ptr(&shared)));
+    if let Some(priority) = config.priority {
+        priority.apply();
+    }
+    if let Some(affinity) = &config.affinity {
+        affinity.apply();
+    }
+    let finish_guard = FinishGuard(&shared);
+    let catch_run = || panic::catch_unwind(AssertUnwindSafe(|| runner(&shared)));
+    match config.panic_policy {
+        PanicPolicy::Propagate => runner(&shared),
+        PanicPolicy::Ignore => while catch_run().is_err() {},
+        PanicPolicy::Abort => {
+            if catch_run().is_err() {
+                process::abort();
+            }
+        }
+        PanicPolicy::Restart => {
+            while catch_run().is_err() {
+                // Replace this thread by a fresh one consuming the same queue.
+                // If we cannot spawn a thread, continue in this one.
+                let mut worker = shared.worker();
+                let new_worker = spawn_worker(
+                    Arc::clone(&runner),
+                    Arc::clone(&shared),
+                    Arc::clone(&config),
+                );
+                if let Ok(thread_handle) = new_worker {
+                    if !worker.detached {
+                        worker.handles.push(thread_handle);
+                    }
+                    // The dropper thread continues in the new thread.
+                    mem::forget(finish_guard);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The drops done in several steps, released when dropped, even on panic: the collections
+/// dropped a chunk at a time, and the futures of `defer_async` polled by a consumer task.
+///
+/// The time spent on each drop is summed, to observe it whole.
+struct Ongoing<S: Deref<Target = Shared>> {
+    shared: S,
+    collections: VecDeque<(Box<dyn DropChunks>, Duration)>,
+    /// The futures being polled, `None` if a dropper thread runs them to completion at once.
+    cleanups: Option<Vec<(Cleanup, &'static str, Duration)>>,
+    /// Number of futures polled since they were sent.
+    polled: usize,
+}
+
+impl<S: Deref<Target = Shared>> Ongoing<S> {
+    fn new(shared: S) -> Self {
+        Self {
+            shared,
+            collections: VecDeque::new(),
+            cleanups: None,
+            polled: 0,
+        }
+    }
+
+    /// The ongoing drops of a consumer task, which polls the futures.
+    fn polling(shared: S) -> Self {
+        let mut ongoing = Self::new(shared);
+        ongoing.cleanups = Some(Vec::new());
+        ongoing
+    }
+
+    /// Returns `true` if no collection is being dropped.
+    fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+
+    /// Returns `true` if no future is being polled.
+    fn cleaned_up(&self) -> bool {
+        self.cleanups.as_ref().is_none_or(Vec::is_empty)
+    }
+
+    /// Run `cleanup` to completion, or keep it to be polled.
+    fn start(&mut self, cleanup: Cleanup, type_name: &'static str) {
+        match &mut self.cleanups {
+            Some(cleanups) => cleanups.push((cleanup, type_name, Duration::ZERO)),
+            None => {
+                let _release = ReleaseGuard(&self.shared, 1);
+                drop_observed(&self.shared, cleanup, type_name, 1);
+            }
+        }
+    }
+
+    /// Poll the futures, or only those sent since the last call if not `all`.
+    fn poll_cleanups(&mut self, cx: &mut Context<'_>, all: bool) {
+        let Some(cleanups) = &mut self.cleanups else {
+            return;
+        };
+        let shared = &*self.shared;
+        let mut index = if all { 0 } else { self.polled };
+        while index < cleanups.len() {
+            let (mut cleanup, type_name, mut elapsed) = cleanups.swap_remove(index);
+            let release = ReleaseGuard(shared, 1);
+            let _watch = shared
+                .watchdog
+                .as_ref()
+                .map(|watchdog| watchdog.watch(type_name, 1));
+            let start = Instant::now();
+            let poll = cleanup.poll(cx);
+            elapsed += start.elapsed();
+            if poll.is_ready() {
+                if shared.timed() {
+                    shared.observe(type_name, 1, elapsed);
+                }
+                drop(release);
+            } else {
+                mem::forget(release);
+                // Put it back in its place.
+                cleanups.push((cleanup, type_name, elapsed));
+                let last = cleanups.len() - 1;
+                cleanups.swap(index, last);
+                index += 1;
+            }
+        }
+        self.polled = cleanups.len();
+    }
+
+    fn push(&mut self, chunks: Box<dyn DropChunks>) {
+        self.collections.push_back((chunks, Duration::ZERO));
+    }
+
+    /// Drop the next chunk of the first collection.
+    fn drop_chunk(&mut self) {
+        let Some((mut chunks, mut elapsed)) = self.collections.pop_front() else {
+            return;
+        };
+        let shared = &*self.shared;
+        let release = ReleaseGuard(shared, 1);
+        let _watch = shared
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.watch(chunks.type_name(), 1));
+        let start = Instant::now();
+        let done = chunks.drop_chunk();
+        elapsed += start.elapsed();
+        if let Some(pacer) = &shared.pacer {
+            pacer.charge(0, 0, start);
+        }
+        if done {
+            if shared.timed() {
+                shared.observe(chunks.type_name(), 1, elapsed);
+            }
+            drop(chunks);
+            drop(release);
+        } else {
+            mem::forget(release);
+            self.collections.push_front((chunks, elapsed));
+        }
+    }
+
+    fn finish(&mut self) {
+        while !self.is_empty() {
+            self.drop_chunk();
+        }
+    }
+
+    /// Drop the collections left at once, and the futures left without completing them.
+    fn clear(&mut self) {
+        if !self.is_empty() {
+            let _release = ReleaseGuard(&self.shared, self.collections.len());
+            self.collections.clear();
+        }
+        if let Some(cleanups) = self
+            .cleanups
+            .as_mut()
+            .filter(|cleanups| !cleanups.is_empty())
+        {
+            let _release = ReleaseGuard(&self.shared, cleanups.len());
+            for (mut cleanup, ..) in cleanups.drain(..) {
+                cleanup.0.take();
+            }
+        }
+    }
+}
+
+impl<S: Deref<Target = Shared>> Drop for Ongoing<S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Handle the messages until the dropper is closed, yielding the thread every `yield_every`
+/// messages.
+fn run<T>(drop_receiver: &Queues<T>, shared: &Shared, yield_every: Option<usize>) {
+    let mut ongoing = Ongoing::new(shared);
+    let mut handled = 0;
+    loop {
+        if let Some(until) = shared.throttled() {
+            thread::sleep(until.saturating_duration_since(Instant::now()));
+        }
+        let message = if ongoing.is_empty() {
+            let message = drop_receiver.try_recv().or_else(|_| {
+                // The values deferred so far have been dropped.
+                shared.end_deferral();
+                drop_receiver.recv()
+            });
+            match message {
+                Ok(message) => message,
+                Err(_) => break,
+            }
+        } else {
+            // Between two chunks, handle the values queued since.
+            match drop_receiver.try_recv() {
+                Ok(message) => {
+                    if message.is_barrier() {
+                        ongoing.finish();
+                    }
+                    message
+                }
+                Err(_) => {
+                    shared.wait_resumed();
+                    ongoing.drop_chunk();
+                    continue;
+                }
+            }
+        };
+        // A paused dropper keeps the message until resumed.
+        shared.wait_resumed();
+        if !handle(message, shared, &mut ongoing) {
+            drain(drop_receiver, shared, &mut ongoing);
+            break;
+        }
+        handled += 1;
+        if Some(handled) == yield_every {
+            handled = 0;
+            // Let the other threads of the machine run.
+            thread::yield_now();
+        }
+    }
+    ongoing.finish();
+}
+
+/// Handle the messages sent after a close, if this consumer is the last one to be closed.
+fn drain<T>(
+    drop_receiver: &Queues<T>,
+    shared: &Shared,
+    ongoing: &mut Ongoing<impl Deref<Target = Shared>>,
+) {
+    // We are closed, but values may have been sent since.
+    // The last thread to be closed drops them here before the channel is closed.
+    if shared.closing.fetch_sub(1, Ordering::SeqCst) == 1 {
+        while let Ok(message) = drop_receiver.try_recv() {
+            handle(message, shared, ongoing);
+        }
+    }
+}
+
+/// Handle the next message or chunk, if any, without waiting.
+fn step<T>(drop_receiver: &Queues<T>, shared: &Shared, ongoing: &mut Ongoing<Arc<Shared>>) -> Step {
+    match shared.deferral() {
+        None => {}
+        Some(Deferral::Paused(until)) => return Step::Paused(until),
+        Some(Deferral::Busy(until)) => return Step::Throttled(until),
+    }
+    if let Some(until) = shared.throttled() {
+        return Step::Throttled(until);
+    }
+    let message = match drop_receiver.try_recv() {
+        Ok(message) => message,
+        // Between two chunks, handle the values queued since.
+        Err(TryRecvError::Empty) if !ongoing.is_empty() => {
+            ongoing.drop_chunk();
+            return Step::Handled;
+        }
+        Err(TryRecvError::Empty) => {
+            // The values deferred so far have been dropped.
+            shared.end_deferral();
+            return Step::Empty;
+        }
+        Err(TryRecvError::Disconnected) => {
+            ongoing.finish();
+            return Step::Closed;
+        }
+    };
+    if message.is_barrier() {
+        ongoing.finish();
+    }
+    // A task cannot block until the other consumers have their marker, it waits in `poll`.
+    if let Message::Flush(marker) = message {
+        marker.arrive(shared);
+        return Step::Flushing(marker);
+    }
+    let open = handle(message, shared, ongoing);
+    if !open {
+        drain(drop_receiver, shared, ongoing);
+        ongoing.finish();
+    }
+    match open {
+        true => Step::Handled,
+        false => Step::Closed,
+    }
+}
+
+/// The consumer of a dropper queue, run by a [`Spawner`](crate::Spawner) instead of a dedicated
+/// thread.
+///
+/// The consumer handles the messages until the queue is empty, then waits to be woken up by a
+/// sender. It completes when the dropper is closed.
+pub struct Consumer {
+    stepper: Stepper,
+    shared: Arc<Shared>,
+    /// The collections being dropped a chunk per step, so the executor runs between two chunks,
+    /// and the futures polled along with the messages.
+    ongoing: Ongoing<Arc<Shared>>,
+    panic_policy: PanicPolicy,
+    /// Number of messages handled before yielding to the executor.
+    budget: usize,
+    /// The flush marker received, waiting for the other consumers to receive theirs.
+    flushing: Option<Arc<FlushMarker>>,
+    /// Set once closed, to complete when the futures left are.
+    closed: bool,
+}
+
+impl Consumer {
+    /// Run the consumer in the current thread, until the dropper is closed.
+    pub fn run(self) {
+        block_on(self)
+    }
+
+    /// Run the consumer in a new thread built by `builder`, after calling `setup` in it.
+    ///
+    /// The thread is joined when the dropper is dropped, as a dedicated dropper thread.
+    pub(crate) fn spawn_thread(
+        self,
+        builder: thread::Builder,
+        setup: impl FnOnce() + Send + 'static,
+    ) -> io::Result<()> {
+        let shared = Arc::clone(&self.shared);
+        let thread_handle = builder.spawn(move || {
+            setup();
+            self.run()
+        })?;
+        shared.worker().handles.push(thread_handle);
+        Ok(())
+    }
+
+    /// Handle the next message, applying the panic policy.
+    ///
+    /// Returns `None` if the consumer must stop because of a panic.
+    fn step(&mut self) -> Option<Step> {
+        let step = || (self.stepper)(&self.shared, &mut self.ongoing);
+        match panic::catch_unwind(AssertUnwindSafe(step)) {
+            Ok(step) => Some(step),
+            Err(payload) => self.panicked(payload).then_some(Step::Handled),
+        }
+    }
+
+    /// Poll the futures sent with `defer_async`, or only the new ones if not `all`, applying the
+    /// panic policy.
+    ///
+    /// Returns `false` if the consumer must stop because of a panic.
+    fn poll_cleanups(&mut self, cx: &mut Context<'_>, all: bool) -> bool {
+        loop {
+            let poll = || self.ongoing.poll_cleanups(cx, all);
+            match panic::catch_unwind(AssertUnwindSafe(poll)) {
+                Ok(()) => return true,
+                // Poll the other futures.
+                Err(payload) => {
+                    if !self.panicked(payload) {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Apply the panic policy, returning `false` if the consumer must stop.
+    fn panicked(&self, payload: Box<dyn Any + Send>) -> bool {
+        match self.panic_policy {
+            PanicPolicy::Propagate => {
+                *lock(&self.shared.panicked) = Some(payload);
+                self.shared.crashed.store(true, Ordering::SeqCst);
+                false
+            }
+            PanicPolicy::Ignore | PanicPolicy::Restart => true,
+            PanicPolicy::Abort => process::abort(),
+        }
+    }
+}
+
+impl Future for Consumer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if !this.poll_cleanups(cx, true) {
+            return Poll::Ready(());
+        }
+        if this.closed {
+            return match this.ongoing.cleaned_up() {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            };
+        }
+        let mut registered = false;
+        let mut handled = 0;
+        loop {
+            if let Some(marker) = this.flushing.take() {
+                // The futures sent before the marker complete first, they wake up the consumer.
+                if !this.ongoing.cleaned_up()
+                    || this
+                        .shared
+                        .poll_until(cx, |shared| marker.all_arrived(shared))
+                        .is_pending()
+                {
+                    this.flushing = Some(marker);
+                    return Poll::Pending;
+                }
+                marker.acknowledge(&this.shared);
+            }
+            match this.step() {
+                Some(Step::Handled) => {
+                    if !this.poll_cleanups(cx, false) {
+                        return Poll::Ready(());
+                    }
+                    registered = false;
+                    handled += 1;
+                    // Let the other tasks of the executor run.
+                    if handled == this.budget {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                }
+                Some(Step::Flushing(marker)) => this.flushing = Some(marker),
+                // Check the queue again once registered, a message may have been sent meanwhile.
+                Some(Step::Empty) if !registered => {
+                    this.shared.register_consumer(cx.waker());
+                    registered = true;
+                }
+                Some(Step::Empty) => return Poll::Pending,
+                Some(Step::Throttled(until)) => {
+                    let waker = cx.waker().clone();
+                    delay::schedule(until, move || waker.wake());
+                    return Poll::Pending;
+                }
+                // A paused consumer waits to be woken up by `resume`, or the deadline.
+                Some(Step::Paused(until)) => {
+                    this.shared.register_consumer(cx.waker());
+                    if let Some(until) = until {
+                        let waker = cx.waker().clone();
+                        delay::schedule(until, move || waker.wake());
+                    }
+                    return Poll::Pending;
+                }
+                Some(Step::Closed) if !this.ongoing.cleaned_up() => {
+                    this.closed = true;
+                    return Poll::Pending;
+                }
+                Some(Step::Closed) | None => return Poll::Ready(()),
+            }
+        }
+    }
+}
+
+impl Drop for Consumer {
+    fn drop(&mut self) {
+        // The collections left are released before the consumer is finished.
+        self.ongoing.clear();
+        // The consumer is finished when completed, or dropped with its runtime.
+        drop(FinishGuard(&self.shared));
+    }
+}
+
+/// The stats of a dropper, read by a collector which must not keep the dropper open.
+#[cfg(feature = "prometheus")]
+pub struct StatsSource(Arc<Shared>);
+
+#[cfg(feature = "prometheus")]
+impl StatsSource {
+    /// A snapshot of the activity of the dropper threads.
+    pub fn stats(&self) -> DropperStats {
+        self.0
+            .counters
+            .snapshot(self.0.pending.load(Ordering::SeqCst))
+    }
+
+    /// The name of the dropper.
+    pub fn name(&self) -> &str {
+        self.0.counters.name()
+    }
+}
+
+/// A future completing once all the threads (or tasks) of a dropper have stopped.
+///
+/// Created with [`Dropper::closed`](crate::Dropper::closed). It resolves to an error if a
+/// dropper thread has been stopped by a panic.
+#[must_use = "futures do nothing unless awaited"]
+pub struct Closed(Arc<Shared>);
+
+impl Closed {
+    /// Returns `true` if the dropper threads have stopped.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.0.finished.load(Ordering::SeqCst)
+    }
+}
+
+impl Future for Closed {
+    type Output = Result<(), ShutdownError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        ready!(self
+            .0
+            .poll_until(cx, |shared| shared.finished.load(Ordering::SeqCst)));
+        match self.0.crashed.load(Ordering::SeqCst) {
+            true => Poll::Ready(Err(ShutdownError::WorkerPanicked)),
+            false => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+/// Run `future` to completion in the current thread.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Run `future` in the current thread until it completes, or `None` once `deadline` is reached.
+#[cfg(feature = "tokio")]
+fn block_on_until<F: Future>(future: F, deadline: Instant) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        thread::park_timeout(deadline - now);
+    }
+}
+
+/// Unpark a thread running a future.
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+/// Drop `to_drop`, reporting it to the observers and stats if any.
+fn drop_observed<V>(shared: &Shared, to_drop: V, type_name: &'static str, count: usize) {
+    let _watch = shared
+        .watchdog
+        .as_ref()
+        .map(|watchdog| watchdog.watch(type_name, count));
+    if shared.timed() {
+        let start = Instant::now();
+        drop(to_drop);
+        shared.observe(type_name, count, start.elapsed());
+    } else {
+        drop(to_drop);
+    }
+}
+
+/// Handle one message, returning `false` if the dropper thread must stop.
+fn handle<T>(
+    message: Message<T>,
+    shared: &Shared,
+    ongoing: &mut Ongoing<impl Deref<Target = Shared>>,
+) -> bool {
+    shared.received.fetch_add(1, Ordering::SeqCst);
+    let (message, bytes) = match message {
+        Message::Sized(message, bytes) => (*message, bytes),
+        message => (message, 0),
+    };
+    let _bytes = BytesGuard(shared, bytes);
+    let count = message.count();
+    shared.drained(count);
+    let paced = match (&shared.pacer, count) {
+        (Some(pacer), 1..) => Some((pacer, Instant::now())),
+        _ => None,
+    };
+    match message {
+        Message::Drop(to_drop) => {
+            shared.queued.fetch_sub(1, Ordering::Relaxed);
+            let _release = ReleaseGuard(shared, 1);
+            drop_observed(shared, to_drop, any::type_name::<T>(), 1);
+        }
+        Message::Erased(to_drop, type_name) => {
+            shared.queued.fetch_sub(1, Ordering::Relaxed);
+            let _release = ReleaseGuard(shared, 1);
+            drop_observed(shared, to_drop, type_name, 1);
+        }
+        Message::Batch(to_drop) => {
+            let count = to_drop.len();
+            shared.queued.fetch_sub(count, Ordering::Relaxed);
+            let _release = ReleaseGuard(shared, count);
+            drop_observed(shared, to_drop, any::type_name::<T>(), count);
+        }
+        Message::ErasedBatch(to_drop, count, type_name) => {
+            shared.queued.fetch_sub(count, Ordering::Relaxed);
+            let _release = ReleaseGuard(shared, count);
+            drop_observed(shared, to_drop, type_name, count);
+        }
+        Message::Chunked(chunks) => {
+            shared.queued.fetch_sub(1, Ordering::Relaxed);
+            ongoing.push(chunks);
+        }
+        Message::Cleanup(cleanup, type_name) => {
+            shared.queued.fetch_sub(1, Ordering::Relaxed);
+            ongoing.start(cleanup, type_name);
+        }
+        Message::Flush(marker) => {
+            marker.arrive(shared);
+            shared.wait_while(|shared| !marker.all_arrived(shared), None);
+            marker.acknowledge(shared);
+        }
+        Message::Sized(..) => unreachable!("The size of the values is taken above"),
+        Message::Close => return false,
+        Message::Wake => {}
+    }
+    if let Some((pacer, started)) = paced {
+        pacer.charge(count, bytes, started);
+    }
+    true
+}
+
+/// Sends values to a dropper without owning it, for the jobs of the timer thread.
+///
+/// Unlike a `WeakDropper`, sending a value doesn't upgrade to a `Dropper`: if the last one is
+/// dropped meanwhile, its `Drop` waits for the value to be sent, it never runs in the thread of
+/// the sender (which only detaches a shared thread left without handles).
+pub struct WeakSender<T: Send> {
+    sink: Weak<Sink<T>>,
+    overflow_policy: OverflowPolicy,
+    shared: Weak<Shared>,
+}
+
+impl<T: Send + 'static> WeakSender<T> {
+    /// Send the object to be drop, see `Dropper::dropout`.
+    ///
+    /// If the dropper is gone, `to_drop` is dropped in the current thread.
+    pub fn dropout(&self, to_drop: T) {
+        self.with_dropper(|dropper| dropper.dropout(to_drop));
+    }
+
+    /// Send several objects to be dropped in one message, see `Dropper::dropout_many`.
+    ///
+    /// If the dropper is gone, `to_drop` is dropped in the current thread.
+    pub fn dropout_many(&self, to_drop: Vec<T>) {
+        self.with_dropper(|dropper| dropper.dropout_many(to_drop));
+    }
+
+    /// Call `send` with a handle of the dropper which doesn't own it, if it still exists.
+    fn with_dropper(&self, send: impl FnOnce(&Dropper<T>)) {
+        let (Some(sink), Some(shared)) = (self.sink.upgrade(), self.shared.upgrade()) else {
+            return;
+        };
+        let mut dropper = Dropper {
+            sink: Some(sink),
+            overflow_policy: self.overflow_policy,
+            shared,
+        };
+        send(&dropper);
+        // Take the sink back, so that the `Drop` of this handle does nothing.
+        let sink = dropper.sink.take().and_then(Arc::into_inner);
+        if let Some(Sink::Attached(owner, _)) = sink {
+            // The shared thread lost all its other handles meanwhile: don't wait for it here.
+            if let Some(owner) = Arc::into_inner(owner) {
+                owner.detach();
+            }
+        }
+    }
+}
+
+impl<T: Send> Clone for WeakSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: Weak::clone(&self.sink),
+            overflow_policy: self.overflow_policy,
+            shared: Weak::clone(&self.shared),
+        }
+    }
+}
+
+impl<T: Send> Drop for Dropper<T> {
+    fn drop(&mut self) {
+        // A shared thread is joined when its owner is dropped.
+        let Some(sink) = self.sink.take() else {
+            return;
+        };
+        if let Sink::Owned(_) = *sink {
+            // The queues are closed once the `WeakSender`s sending a value are done.
+            drop(sink);
+            // The remaining values are dropped before the threads stop.
+            self.shared.released.store(true, Ordering::SeqCst);
+            self.shared.notify();
+            self.shared.resume();
+            // Consumer tasks are not waited for, they may run on the current thread.
+            // The threads of a `ThreadSpawner` are joined.
+            self.shared.wake_consumers();
+            let worker = self.shared.worker();
+            let join = !worker.tasks || !worker.handles.is_empty();
+            drop(worker);
+            if join {
+                let _ = self.shared.join();
+            }
+        }
+    }
+}
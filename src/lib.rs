@@ -2,9 +2,111 @@
 //!
 //! Dropout is inspired by [defer-drop](https://docs.rs/defer-drop) and (as defer-drop itself) by [https://abramov.io/rust-dropping-things-in-another-thread](https://abramov.io/rust-dropping-things-in-another-thread)
 //!
-//! See [`Dropper`] for details.
+//! See [`Dropper`] for details, or [`dropout()`] to use a process-global dropper.
 
-use std::sync::Arc;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::io;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+#[cfg(feature = "tokio-util")]
+use tokio_util::sync::CancellationToken;
+
+mod affinity;
+mod any;
+#[cfg(feature = "tokio")]
+mod async_dropper;
+#[cfg(feature = "async-std")]
+mod async_std_dropper;
+mod batch;
+mod buffered;
+mod builder;
+#[cfg(feature = "prometheus")]
+mod collector;
+pub mod compat;
+mod completion;
+mod deferred;
+mod delay;
+mod event;
+mod fence;
+mod global;
+mod inner;
+mod outlet;
+mod pool;
+mod priority;
+mod rate;
+mod recycle;
+mod registry;
+mod sharded;
+mod shutdown;
+#[cfg(feature = "futures")]
+mod sink;
+mod size;
+mod spawner;
+mod stats;
+mod ttl;
+mod watchdog;
+mod weak;
+
+pub use any::AnyDropper;
+#[cfg(feature = "tokio")]
+pub use async_dropper::{blocking, AsyncDropper};
+#[cfg(feature = "async-std")]
+pub use async_std_dropper::AsyncStdDropper;
+pub use batch::Batch;
+pub use buffered::BufferedDropper;
+pub use builder::DropperBuilder;
+#[cfg(feature = "prometheus")]
+pub use collector::DropperCollector;
+pub use completion::{DropTicket, Dropped};
+pub use deferred::{Deferred, DropArc};
+#[cfg(feature = "derive")]
+pub use dropout_derive::DeferDrop;
+pub use event::DropEvent;
+#[cfg(feature = "futures")]
+pub use event::DropEvents;
+pub use fence::{Fence, FencedDropper};
+pub use global::{dropout, global};
+pub use inner::{Closed, Consumer};
+pub use outlet::{DropOutlet, DropoutExt, InlineDrop};
+pub use pool::DropperPool;
+pub use recycle::{Recycle, Recycler};
+pub use registry::DropperRegistry;
+pub use sharded::ShardedPool;
+pub use shutdown::{ShutdownError, ShutdownOutcome};
+pub use size::SizeHint;
+#[cfg(feature = "async-std")]
+pub use spawner::AsyncStdSpawner;
+#[cfg(feature = "futures")]
+pub use spawner::FuturesSpawner;
+#[cfg(feature = "tokio")]
+pub use spawner::TokioSpawner;
+pub use spawner::{Spawner, ThreadSpawner};
+pub use stats::{DropperStats, LatencyHistogram, SlowDrop};
+pub use ttl::ExpiryHandle;
+pub use watchdog::StuckDrop;
+pub use weak::WeakDropper;
+
+/// Create a new [`Dropper`], the type of dropped values being inferred from its usage.
+///
+/// This is the same as [`Dropper::new`].
+///
+/// # Panics
+///
+/// Panics if the dropper thread cannot be created. See [`Dropper::try_new`].
+///
+/// # Example
+///
+/// ```
+/// let dropper = dropout::new_dropper();
+/// dropper.dropout(vec![0u8; 1024]);
+/// ```
+#[inline]
+pub fn new_dropper<T: Send + 'static>() -> Dropper<T> {
+    Dropper::new()
+}
 
 /// Dropper can send object to a background thread to be dropped there.
 /// Useful when the object takes a long time to drop and you don't want your (main) thread
@@ -12,15 +114,15 @@ use std::sync::Arc;
 ///
 /// # Notes:
 ///
-/// There is one dropper thread per `Dropper`. Dropped values are enqueued in an
-/// unbounded channel to be consumed by this thread; if you send more
-/// value than the thread can handle, this will cause unbounded memory
-/// consumption. There is currently no way for the thread to signal or block
-/// if it is overwhelmed.
+/// There is one dropper thread per `Dropper` (unless it comes from a [`DropperRegistry`]).
+/// Dropped values are enqueued in a channel to be consumed by this thread.
+/// By default this channel is unbounded; if you send more value than the thread can handle,
+/// this will cause unbounded memory consumption. Use [`Dropper::with_capacity`] to put a hard limit on the number of
+/// values waiting to be dropped.
 ///
 /// The objects are guaranteed to be destructed in the order received through a
 /// channel, which means that objects sent from a single thread will be
-/// destructed in order. However, there is no guarantee about the ordering of
+/// destructed in order (unless there are several dropper threads, see [`Dropper::with_workers`]). However, there is no guarantee about the ordering of
 /// interleaved values from different threads.
 /// Value send to be dropped are guaranted to be dropped at a moment as `Dropper` itself
 /// wait for all values to be dropped when it is been dropped.
@@ -62,73 +164,1629 @@ use std::sync::Arc;
 ///
 /// assert!(dropout_time < std_time);
 /// ```
-pub struct Dropper<T: Send>(Arc<inner::Dropper<T>>);
+pub struct Dropper<T: Send>(
+    Arc<inner::Dropper<T>>,
+    #[cfg(feature = "futures")] sink::Slot<T>,
+);
+
+impl<T: Send> Dropper<T> {
+    /// Create a handle of `inner`.
+    #[inline]
+    pub(crate) fn from_inner(inner: Arc<inner::Dropper<T>>) -> Self {
+        Self(
+            inner,
+            #[cfg(feature = "futures")]
+            sink::Slot::default(),
+        )
+    }
+}
 
 impl<T: Send + 'static> Dropper<T> {
     /// Create a new Dropper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dropper thread cannot be created. See [`try_new`](Self::try_new).
     #[inline]
     pub fn new() -> Self {
-        Self(Arc::new(inner::Dropper::new()))
+        Self::try_new().expect("Should succeed to create thread")
+    }
+
+    /// Create a new Dropper, returning an error if the dropper thread cannot be created.
+    #[inline]
+    pub fn try_new() -> io::Result<Self> {
+        DropperBuilder::new().build()
+    }
+
+    /// Create a new Dropper whose consumer is run by `spawner` instead of a dedicated thread.
+    ///
+    /// Unless `spawner` is a [`ThreadSpawner`], the consumer is not waited for when the last
+    /// handle is dropped, see [`Spawner`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spawner` fails to spawn the consumer. See [`DropperBuilder::build_with`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, ThreadSpawner};
+    /// let dropper = Dropper::with_spawner(ThreadSpawner::new().name("my-dropper"));
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush();
+    /// ```
+    #[inline]
+    pub fn with_spawner(spawner: impl Spawner) -> Self {
+        DropperBuilder::new()
+            .build_with(spawner)
+            .expect("Should succeed to spawn the consumer")
+    }
+
+    /// Create a [`DropperBuilder`] to configure a new Dropper.
+    #[inline]
+    pub fn builder() -> DropperBuilder {
+        DropperBuilder::new()
+    }
+
+    /// Get a dropper sending its values to the process-global dropper thread.
+    ///
+    /// Unlike [`Dropper::new`], no thread is created: all the droppers returned by `shared`
+    /// (whatever their type) and [`global`] use the same thread. The values of a given dropper
+    /// are still dropped in order, but they wait behind the values of all the other ones.
+    /// See [`DropperRegistry`] for the behavior of such droppers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global dropper thread doesn't exist yet and cannot be created, see
+    /// [`global`](crate::global).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let vec_dropper = Dropper::<Vec<u8>>::shared();
+    /// let string_dropper = Dropper::<String>::shared();
+    /// vec_dropper.dropout(vec![0; 1024]);
+    /// string_dropper.dropout(String::from("Hello"));
+    /// string_dropper.flush();
+    /// assert_eq!(vec_dropper.pending(), 0);
+    /// ```
+    pub fn shared() -> Self {
+        global::registry().dropper()
+    }
+
+    /// Create a dropper sending its values to the threads of `pool`.
+    ///
+    /// No thread is created: the values are dropped by the threads of the pool, with the values
+    /// sent to the pool or to the other droppers attached to it. So, like for the pool itself,
+    /// the values are not dropped in order.
+    /// See [`DropperRegistry`] for the behavior of droppers sharing threads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperPool};
+    /// # use std::collections::HashMap;
+    /// let pool = DropperPool::new(2);
+    /// let vec_dropper = Dropper::<Vec<u8>>::spawn_on(&pool);
+    /// let map_dropper = Dropper::<HashMap<u32, String>>::spawn_on(&pool);
+    /// vec_dropper.dropout(vec![0; 1024]);
+    /// map_dropper.dropout(HashMap::new());
+    /// pool.flush();
+    /// assert_eq!(vec_dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn spawn_on(pool: &DropperPool) -> Self {
+        Self::from_inner(Arc::new(inner::Dropper::attached(&pool.0)))
+    }
+
+    /// Create a dropper sending its values to the threads of `pool` through its own queue, served
+    /// with `weight`.
+    ///
+    /// The threads of the pool take the values of the queues in turn: up to `weight` values from
+    /// this dropper, then up to one value from the pool itself (shared by the droppers created
+    /// with [`spawn_on`](Self::spawn_on)), then from the next weighted dropper, and so on. So a
+    /// dropper flooded with values cannot starve the others: each gets a share of the drops in
+    /// proportion to its weight while it has values queued.
+    ///
+    /// The queue of the dropper has the capacity of the queue of the pool. Values sent with
+    /// another priority than [`Priority::Normal`] use the queues of the pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperPool};
+    /// let pool = DropperPool::new(1);
+    /// let noisy = Dropper::<Vec<u8>>::spawn_on_weighted(&pool, 1);
+    /// let important = Dropper::<String>::spawn_on_weighted(&pool, 4);
+    /// for _ in 0..1000 {
+    ///     noisy.dropout(vec![0; 1024]);
+    /// }
+    /// // Not stuck behind the values of `noisy`.
+    /// important.dropout(String::from("cache entry"));
+    /// pool.flush();
+    /// assert_eq!(pool.pending(), 0);
+    /// ```
+    pub fn spawn_on_weighted(pool: &DropperPool, weight: usize) -> Self {
+        assert!(weight > 0, "weight must be at least 1");
+        Self::from_inner(Arc::new(inner::Dropper::attached_weighted(&pool.0, weight)))
+    }
+
+    /// Create a new Dropper with a bounded queue.
+    ///
+    /// At most `capacity` values can wait to be dropped in the background thread.
+    /// If the queue is full, [`dropout`](Self::dropout) drops the value in the current thread,
+    /// so producers cannot outrun the dropper thread indefinitely.
+    ///
+    /// A `capacity` of 0 means values are only sent when the dropper thread is waiting for one.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_overflow_policy(capacity, OverflowPolicy::DropInline)
+    }
+
+    /// Create a new Dropper with a bounded queue and the given [`OverflowPolicy`].
+    ///
+    /// The policy defines what [`dropout`](Self::dropout) does when the queue is full.
+    #[inline]
+    pub fn with_overflow_policy(capacity: usize, policy: OverflowPolicy) -> Self {
+        DropperBuilder::new()
+            .capacity(capacity)
+            .overflow_policy(policy)
+            .build()
+            .expect("Should succeed to create thread")
+    }
+
+    /// Create a new Dropper with `workers` threads consuming its queue.
+    ///
+    /// Use this when one thread cannot keep up with the values to drop. Values are then dropped
+    /// concurrently: there is no guarantee about the order in which they are dropped, even for
+    /// values sent from a single thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0 or if the dropper threads cannot be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper = Dropper::with_workers(4);
+    /// for i in 0..100 {
+    ///     let map: HashMap<usize, Vec<usize>> = (0..1000).map(|v| (v, vec![v + i])).collect();
+    ///     dropper.dropout(map);
+    /// }
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn with_workers(workers: usize) -> Self {
+        DropperBuilder::new()
+            .workers(workers)
+            .build()
+            .expect("Should succeed to create threads")
+    }
+
+    /// Create a new Dropper calling the [`Watermarks`] callbacks when the number of
+    /// pending values crosses the watermarks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, Watermarks};
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// let paused = Arc::new(AtomicBool::new(false));
+    /// let (pause, resume) = (Arc::clone(&paused), Arc::clone(&paused));
+    /// let dropper = Dropper::with_watermarks(
+    ///     Watermarks::new(1000, 100)
+    ///         .on_high(move || pause.store(true, Ordering::Relaxed))
+    ///         .on_low(move || resume.store(false, Ordering::Relaxed)),
+    /// );
+    /// for i in 0..10_000 {
+    ///     while paused.load(Ordering::Relaxed) {
+    ///         std::thread::yield_now();
+    ///     }
+    ///     dropper.dropout(vec![i; 16]);
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_watermarks(watermarks: Watermarks) -> Self {
+        DropperBuilder::new()
+            .watermarks(watermarks)
+            .build()
+            .expect("Should succeed to create thread")
     }
 
     /// Send a value to be dropped in another thread.
     ///
     /// If somehow the receiving part is closed (probably because of a panic in a previous object drop),
     /// `to_drop` value will be drop in the current thread.
+    ///
+    /// If the queue is full (see [`with_capacity`](Self::with_capacity)), the [`OverflowPolicy`]
+    /// of the dropper is applied.
     #[inline]
     pub fn dropout(&self, to_drop: T) {
         self.0.dropout(to_drop)
     }
-}
 
-impl<T: Send + 'static> Default for Dropper<T> {
-    fn default() -> Self {
-        Self::new()
+    /// Send a value to be dropped in another thread, before the values of lower priority.
+    ///
+    /// Each priority has its own queue (with the capacity of the dropper), the dropper thread
+    /// drops the values of the [`High`](Priority::High) queue first, then the
+    /// [`Normal`](Priority::Normal) one (the values sent with [`dropout`](Self::dropout)), then
+    /// the [`Low`](Priority::Low) one. A value being dropped is not interrupted, and
+    /// [`flush`](Self::flush) waits for the values of all priorities.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, Priority};
+    /// let dropper = Dropper::new();
+    /// for _ in 0..100 {
+    ///     dropper.dropout_with_priority(vec![0u8; 1024 * 1024], Priority::Low);
+    /// }
+    /// // Dropped before the bulk of the values sent above.
+    /// dropper.dropout_with_priority(vec![0u8; 1024], Priority::High);
+    /// dropper.flush();
+    /// ```
+    #[inline]
+    pub fn dropout_with_priority(&self, to_drop: T, priority: Priority) {
+        self.0.dropout_with_priority(to_drop, priority)
     }
-}
 
-impl<T: Send + 'static> Clone for Dropper<T> {
-    fn clone(&self) -> Self {
-        Self(Arc::clone(&self.0))
+    /// Send a value to be dropped in another thread, `bytes` being the memory it releases.
+    ///
+    /// The size is a hint for [`DropperBuilder::max_bytes_per_second`] and the byte counts of
+    /// the [`stats`](Self::stats), it is not checked. Otherwise, this is the same as
+    /// [`dropout`](Self::dropout).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .max_bytes_per_second(64 * 1024 * 1024)
+    ///     .build()
+    ///     .unwrap();
+    /// let buffer = vec![0u8; 1024 * 1024];
+    /// let bytes = buffer.capacity();
+    /// dropper.dropout_sized(buffer, bytes);
+    /// ```
+    #[inline]
+    pub fn dropout_sized(&self, to_drop: T, bytes: usize) {
+        self.0.dropout_sized(to_drop, bytes)
+    }
+
+    /// Send a value to be dropped in another thread, its size being given by its [`SizeHint`].
+    ///
+    /// This is the same as [`dropout_sized`](Self::dropout_sized) with the size hint of the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// dropper.pause();
+    /// dropper.dropout_measured(vec![0u64; 1024]);
+    /// assert_eq!(dropper.stats().bytes_pending, 8 * 1024);
+    /// dropper.resume();
+    /// dropper.flush();
+    /// assert_eq!(dropper.stats().bytes_dropped, 8 * 1024);
+    /// ```
+    #[inline]
+    pub fn dropout_measured(&self, to_drop: T)
+    where
+        T: SizeHint,
+    {
+        let bytes = to_drop.size_hint();
+        self.0.dropout_sized(to_drop, bytes)
     }
-}
 
-mod inner {
-    use crossbeam_channel::{unbounded, Sender};
-    use std::thread;
+    /// Try to send a value to be dropped in another thread.
+    ///
+    /// If the queue is full (see [`with_capacity`](Self::with_capacity)) or the receiving part is
+    /// closed, the value is given back to the caller, which can decide what to do with it
+    /// (drop it inline, retry later, ...).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::with_capacity(0);
+    /// // No room in the queue (unless the thread is already waiting for a value).
+    /// if let Err(value) = dropper.try_dropout(vec![1, 2, 3]) {
+    ///     assert_eq!(value, vec![1, 2, 3]);
+    /// }
+    /// ```
+    #[inline]
+    pub fn try_dropout(&self, to_drop: T) -> Result<(), T> {
+        self.0.try_dropout(to_drop)
+    }
+
+    /// Send a value to be dropped in another thread, waiting for room in the queue if needed.
+    ///
+    /// With a bounded queue (see [`with_capacity`](Self::with_capacity)), this blocks the current
+    /// thread until the dropper thread has consumed enough values. This provides backpressure
+    /// to producers instead of dropping values inline. It can be called from many threads at once.
+    ///
+    /// If somehow the receiving part is closed, `to_drop` value will be drop in the current thread.
+    #[inline]
+    pub fn dropout_blocking(&self, to_drop: T) {
+        self.0.dropout_blocking(to_drop)
+    }
 
-    pub struct Dropper<T: Send> {
-        drop_sender: Option<Sender<T>>,
-        thread_handle: Option<thread::JoinHandle<()>>,
+    /// Send a value to be dropped in another thread, waiting at most `timeout` for room in the queue.
+    ///
+    /// If there is still no room after `timeout` or if the receiving part is closed,
+    /// the value is given back to the caller.
+    #[inline]
+    pub fn dropout_timeout(&self, to_drop: T, timeout: Duration) -> Result<(), T> {
+        self.0.dropout_timeout(to_drop, timeout)
     }
 
-    impl<T: Send + 'static> Dropper<T> {
-        pub fn new() -> Self {
-            let (drop_sender, drop_receiver) = unbounded();
-            let thread_handle = thread::Builder::new()
-                .name("Dropout".into())
-                .spawn(move || while let Ok(_) = drop_receiver.recv() {})
-                .expect("Should succeed to create thread");
-            Self {
-                drop_sender: Some(drop_sender),
-                thread_handle: Some(thread_handle),
-            }
+    /// Send a value to be dropped in another thread, waiting for room in the queue without
+    /// blocking the current thread. Used by the async droppers.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    #[inline]
+    pub(crate) async fn dropout_async(&self, to_drop: T) {
+        self.0.dropout_async(to_drop).await
+    }
+
+    /// Send a value to be dropped in another thread once `delay` has elapsed.
+    ///
+    /// The value is held by a process-global timer thread until then, for grace periods (an
+    /// evicted cache entry still used by readers, ...). It is not counted as
+    /// [`pending`](Self::pending) before being sent, so [`flush`](Self::flush) doesn't wait for
+    /// it. If all the `Dropper` handles are gone when the delay has elapsed, the value is
+    /// dropped in the timer thread. Values still held when the process exits are never dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::time::Duration;
+    /// let dropper = Dropper::new();
+    /// dropper.dropout_after(vec![0u8; 1024], Duration::from_millis(10));
+    /// assert_eq!(dropper.pending(), 0);
+    /// std::thread::sleep(Duration::from_millis(100));
+    /// dropper.flush();
+    /// ```
+    #[inline]
+    pub fn dropout_after(&self, to_drop: T, delay: Duration) {
+        self.dropout_at(to_drop, Instant::now() + delay)
+    }
+
+    /// Send a value to be dropped in another thread once `deadline` is reached.
+    ///
+    /// This is the same as [`dropout_after`](Self::dropout_after), with an absolute deadline
+    /// (the expiry of a lease, ...). A deadline already reached sends the value at once to the
+    /// dropper thread, through the timer thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::time::{Duration, Instant};
+    /// let dropper = Dropper::new();
+    /// let expiry = Instant::now() + Duration::from_millis(10);
+    /// dropper.dropout_at(vec![0u8; 1024], expiry);
+    /// dropper.dropout_at(vec![1u8; 1024], expiry);
+    /// ```
+    pub fn dropout_at(&self, to_drop: T, deadline: Instant) {
+        let sender = self.0.weak_sender();
+        delay::schedule(deadline, move || sender.dropout(to_drop));
+    }
+
+    /// Send a value to be dropped in another thread once its time-to-live has elapsed, returning
+    /// a handle to postpone or cancel its expiry.
+    ///
+    /// This is the usual expiry of cache entries or sessions: see [`ExpiryHandle`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::time::Duration;
+    /// let dropper = Dropper::new();
+    /// let entry = dropper.expire_after(vec![0u8; 1024], Duration::from_millis(10));
+    /// std::thread::sleep(Duration::from_millis(100));
+    /// assert!(entry.is_expired());
+    /// assert_eq!(entry.cancel(), None);
+    /// ```
+    #[inline]
+    pub fn expire_after(&self, to_drop: T, ttl: Duration) -> ExpiryHandle<T> {
+        ExpiryHandle::new(to_drop, ttl, self.0.weak_sender())
+    }
+
+    /// Send a value to be dropped in another thread, returning a future completing once it has
+    /// been dropped.
+    ///
+    /// This is for the cases where a later work must not start before a resource held by the
+    /// value (a file lock, a socket, ...) is released. The future completes even if the value is
+    /// finally dropped in the current thread (see [`dropout`](Self::dropout)) or if its drop
+    /// panics. It works with any async runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let dropper = Dropper::new();
+    /// let lock_file = std::env::temp_dir().join("dropout-notified.lock");
+    /// let file = std::fs::File::create(&lock_file).unwrap();
+    /// dropper.dropout_notified(file).await;
+    /// // The file is closed now.
+    /// std::fs::remove_file(&lock_file).unwrap();
+    /// # }
+    /// ```
+    pub fn dropout_notified(&self, to_drop: T) -> Dropped {
+        let (dropped, completer) = Dropped::new();
+        self.defer(move || {
+            let _completer = completer;
+            drop(to_drop);
+        });
+        dropped
+    }
+
+    /// Send a value to be dropped in another thread, returning a ticket to wait for its drop.
+    ///
+    /// This is the blocking counterpart of [`dropout_notified`](Self::dropout_notified), for
+    /// example to re-open a resource the old value still holds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let lock_file = std::env::temp_dir().join("dropout-tracked.lock");
+    /// let file = std::fs::File::create(&lock_file).unwrap();
+    /// let ticket = dropper.dropout_tracked(file);
+    /// ticket.wait();
+    /// assert!(ticket.is_dropped());
+    /// // The file is closed now.
+    /// std::fs::remove_file(&lock_file).unwrap();
+    /// ```
+    pub fn dropout_tracked(&self, to_drop: T) -> DropTicket {
+        let (ticket, completer) = DropTicket::new();
+        self.defer(move || {
+            let _completer = completer;
+            drop(to_drop);
+        });
+        ticket
+    }
+
+    /// Send a value to be dropped in another thread, returning a receiver notified once it has
+    /// been dropped.
+    ///
+    /// This is a lighter alternative to [`dropout_tracked`](Self::dropout_tracked) when the caller
+    /// only needs to be told about the drop: a `()` is sent on the channel once the value has been
+    /// dropped. If its drop panics, nothing is sent and the receiver is disconnected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let ack = dropper.dropout_ack(vec![0u8; 1024]);
+    /// // Do other things while the value is dropped, then wait for it.
+    /// ack.recv().unwrap();
+    /// ```
+    pub fn dropout_ack(&self, to_drop: T) -> mpsc::Receiver<()> {
+        let (ack_sender, ack_receiver) = mpsc::sync_channel(1);
+        self.defer(move || {
+            drop(to_drop);
+            let _ = ack_sender.send(());
+        });
+        ack_receiver
+    }
+
+    /// Send a value to be dropped in another thread, returning its sequence number.
+    ///
+    /// The values sent with this method, through any handle of the dropper, get increasing
+    /// sequence numbers. [`wait_for`](Self::wait_for) then waits for all of them up to a given
+    /// sequence number to be dropped, even if the dropper threads drop them out of order (with
+    /// several [`workers`](DropperBuilder::workers), priorities, ...). This orders the release of
+    /// resources like file locks or temporary directories: once `wait_for(seq)` returns, all the
+    /// values up to `seq` are gone.
+    ///
+    /// Like with [`defer`](Self::defer), the value goes through the queue boxed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let first = dropper.dropout_seq(vec![0u8; 1024]);
+    /// let second = dropper.dropout_seq(vec![1u8; 1024]);
+    /// assert!(first < second);
+    /// dropper.wait_for(second);
+    /// ```
+    #[inline]
+    pub fn dropout_seq(&self, to_drop: T) -> u64 {
+        self.0.dropout_seq(to_drop)
+    }
+
+    /// Block until the values sent with [`dropout_seq`](Self::dropout_seq) up to `sequence`
+    /// (included) are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sequence` was not returned by `dropout_seq` on this dropper.
+    #[inline]
+    pub fn wait_for(&self, sequence: u64) {
+        self.0.wait_for(sequence)
+    }
+
+    /// Send a value to be dropped in another thread, boxing it first.
+    ///
+    /// Values sent with [`dropout`](Self::dropout) are moved through the channel, which copies
+    /// them a few times. For very large values (big arrays, enums with a huge variant, ...), this
+    /// method moves the value to the heap once and only sends the pointer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// dropper.dropout_boxed([0u8; 64 * 1024]);
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn dropout_boxed(&self, to_drop: T) {
+        self.0.dropout_boxed(to_drop)
+    }
+
+    /// Send all the values of `to_drop` to be dropped in another thread.
+    ///
+    /// The values are sent together, in one message: this avoids a channel operation
+    /// (and possibly a wakeup of the dropper thread) per value. They count as one value for
+    /// the queue capacity (see [`with_capacity`](Self::with_capacity)) but each of them counts in
+    /// [`pending`](Self::pending). If the queue is full, the [`OverflowPolicy`] is applied to
+    /// all the values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let heavy: Vec<Vec<u8>> = (0..100).map(|_| vec![0; 1024]).collect();
+    /// dropper.dropout_iter(heavy.into_iter().filter(|v| !v.is_empty()));
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn dropout_iter(&self, to_drop: impl IntoIterator<Item = T>) {
+        self.0.dropout_iter(to_drop)
+    }
+
+    /// Send a `Vec` of values to be dropped in another thread.
+    ///
+    /// The whole `Vec` is sent in one message and its elements are dropped in the dropper thread.
+    /// This is the same as [`dropout_iter`](Self::dropout_iter), without collecting the values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let heavy: Vec<Vec<u8>> = (0..100).map(|_| vec![0; 1024]).collect();
+    /// dropper.dropout_many(heavy);
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn dropout_many(&self, to_drop: Vec<T>) {
+        self.0.dropout_many(to_drop)
+    }
+
+    /// Start a [`Batch`] of values, sent in one message when the batch is dropped or committed.
+    #[inline]
+    pub fn batch(&self) -> Batch<'_, T> {
+        Batch::new(self)
+    }
+
+    /// Create a [`FencedDropper`], a handle able to wait for the values sent through it only.
+    #[inline]
+    pub fn fenced(&self) -> FencedDropper<T> {
+        FencedDropper::new(self.clone())
+    }
+
+    /// Create a [`BufferedDropper`] sending values to this dropper in batches.
+    #[inline]
+    pub fn buffered(&self) -> BufferedDropper<T> {
+        BufferedDropper::new(self.clone())
+    }
+
+    /// Move all the values of `values` to be dropped in another thread, leaving it empty.
+    ///
+    /// The values are sent in one message, see [`dropout_many`](Self::dropout_many).
+    /// `values` is left empty and can be reused (its allocation moves with the values).
+    /// For a `HashMap`, see [`dropout_drain_map`](Dropper::dropout_drain_map).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let mut buffer: Vec<Vec<u8>> = (0..100).map(|_| vec![0; 1024]).collect();
+    /// dropper.dropout_drain(&mut buffer);
+    /// assert!(buffer.is_empty());
+    /// buffer.push(vec![1; 1024]);
+    /// ```
+    #[inline]
+    pub fn dropout_drain(&self, values: &mut Vec<T>) {
+        self.dropout_many(std::mem::take(values))
+    }
+
+    /// Clear `values`, dropping its elements in another thread but keeping its allocation.
+    ///
+    /// Unlike [`dropout_drain`](Self::dropout_drain), the elements are moved to a new `Vec` of
+    /// the right size and `values` keeps its capacity, so it can be refilled without
+    /// reallocating. Moving the elements is fast, only their drop is deferred.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// let mut buffer: Vec<Vec<u8>> = Vec::with_capacity(100);
+    /// for _ in 0..10 {
+    ///     buffer.extend((0..100).map(|_| vec![0; 1024]));
+    ///     dropper.dropout_clear_vec(&mut buffer);
+    ///     assert!(buffer.is_empty());
+    ///     assert!(buffer.capacity() >= 100);
+    /// }
+    /// ```
+    #[inline]
+    pub fn dropout_clear_vec(&self, values: &mut Vec<T>) {
+        self.dropout_iter(values.drain(..))
+    }
+
+    /// Swap a default value into `slot` and send the old one to be dropped in another thread.
+    ///
+    /// This is handy to clear a big collection field (`HashMap`, `BTreeMap`, `String`, ...)
+    /// without waiting for its drop. It is the same as
+    /// [`dropout_replace(slot, T::default())`](Self::dropout_replace).
+    /// For a `Vec` of values, see also [`dropout_drain`](Self::dropout_drain).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper = Dropper::new();
+    /// let mut cache: HashMap<u32, Vec<u8>> = (0..100).map(|i| (i, vec![0; 1024])).collect();
+    /// dropper.dropout_swap(&mut cache);
+    /// assert!(cache.is_empty());
+    /// cache.insert(1, vec![1; 1024]);
+    /// ```
+    #[inline]
+    pub fn dropout_swap(&self, slot: &mut T)
+    where
+        T: Default,
+    {
+        self.dropout(std::mem::take(slot))
+    }
+
+    /// Take the value out of `slot`, if any, and send it to be dropped in another thread.
+    ///
+    /// This mirrors [`Option::take`], for fields holding heavy state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// struct Session {
+    ///     state: Option<Vec<u8>>,
+    /// }
+    ///
+    /// let dropper = Dropper::new();
+    /// let mut session = Session { state: Some(vec![0; 1024]) };
+    /// dropper.dropout_take(&mut session.state);
+    /// assert!(session.state.is_none());
+    /// dropper.dropout_take(&mut session.state); // Nothing to send.
+    /// ```
+    #[inline]
+    pub fn dropout_take(&self, slot: &mut Option<T>) {
+        if let Some(value) = slot.take() {
+            self.dropout(value)
         }
+    }
 
-        /// Send the object to be drop.
-        ///
-        /// If somehow the receiving part is closed (probably because of a panic in a previous object drop),
-        /// `to_drop` will be drop in the current thread.
-        #[inline]
-        pub fn dropout(&self, to_drop: T) {
-            let _ = self.drop_sender.as_ref().unwrap().send(to_drop);
+    /// Put `new` in `slot` and send the old value to be dropped in another thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper = Dropper::new();
+    /// let mut cache: HashMap<u32, Vec<u8>> = (0..100).map(|i| (i, vec![0; 1024])).collect();
+    /// let fresh: HashMap<u32, Vec<u8>> = (0..10).map(|i| (i, vec![1; 1024])).collect();
+    /// dropper.dropout_replace(&mut cache, fresh);
+    /// assert_eq!(cache.len(), 10);
+    /// ```
+    #[inline]
+    pub fn dropout_replace(&self, slot: &mut T, new: T) {
+        self.dropout(std::mem::replace(slot, new))
+    }
+
+    /// Drop an `Arc`, sending the value to be dropped in another thread if this was the last
+    /// strong reference.
+    ///
+    /// Dropping another reference only decrements the reference count, which is cheap:
+    /// the value is then not sent, avoiding the cost of the channel. This is race free
+    /// (see [`Arc::into_inner`]): if several threads drop their reference concurrently,
+    /// exactly one of them sends the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::sync::Arc;
+    /// let dropper = Dropper::new();
+    /// let shared = Arc::new(vec![0u8; 1024]);
+    /// let other = Arc::clone(&shared);
+    /// dropper.dropout_arc(shared); // Only decrements the count.
+    /// assert_eq!(dropper.pending(), 0);
+    /// dropper.dropout_arc(other); // The vec is dropped in the dropper thread.
+    /// ```
+    #[inline]
+    pub fn dropout_arc(&self, to_drop: Arc<T>) {
+        if let Some(value) = Arc::into_inner(to_drop) {
+            self.dropout(value)
         }
     }
 
-    impl<T: Send> Drop for Dropper<T> {
-        fn drop(&mut self) {
-            drop(self.drop_sender.take());
-            self.thread_handle.take().map(|h| h.join());
+    /// Send a huge collection to be dropped in another thread, `chunk_size` items at a time.
+    ///
+    /// Dropping a collection with millions of entries can take a long time, during which the
+    /// dropper thread cannot drop anything else. With this method, the dropper thread also drops
+    /// the values queued after the collection between two chunks, so they don't wait for it.
+    ///
+    /// The collection is still dropped before the next [`flush`](Self::flush) (or shutdown)
+    /// completes and counts as one value in [`pending`](Self::pending) until it is fully dropped.
+    /// Any collection can be sent (its items are dropped while iterating over it), whatever the
+    /// type of values of this dropper.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper: Dropper<HashMap<usize, Vec<usize>>> = Dropper::new();
+    /// let huge: HashMap<usize, Vec<usize>> = (0..100_000).map(|v| (v, vec![v])).collect();
+    /// dropper.dropout_chunked(huge, 10_000);
+    /// dropper.dropout(HashMap::from([(0, vec![0])])); // Doesn't wait for `huge` to be dropped.
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn dropout_chunked<I>(&self, to_drop: I, chunk_size: usize)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+    {
+        self.0.dropout_chunked(to_drop, chunk_size)
+    }
+
+    /// Send a closure to be run in another thread.
+    ///
+    /// This is useful for cleanup jobs which are not just dropping a value (flushing then closing
+    /// a file, ...). The closure is handled like a value sent with [`dropout`](Self::dropout):
+    /// it is run in order with the dropped values, counted as [`pending`](Self::pending) until
+    /// it has run and, if it cannot be sent, it is run in the current thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// let dropper = Dropper::<Vec<u8>>::new();
+    /// let done = Arc::new(AtomicBool::new(false));
+    /// let job_done = Arc::clone(&done);
+    /// dropper.defer(move || job_done.store(true, Ordering::SeqCst));
+    /// dropper.flush();
+    /// assert!(done.load(Ordering::SeqCst));
+    /// ```
+    #[inline]
+    pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+        self.0.defer(f)
+    }
+
+    /// Send a future to be run to completion in another thread.
+    ///
+    /// Rust has no async drop: this is for values whose cleanup is asynchronous (an
+    /// `async fn close(self)`, ...). A consumer running as a task (see
+    /// [`DropperBuilder::build_with`] and `AsyncDropper`) polls the future along with the other
+    /// messages, so it runs on the executor of the consumer. A dedicated dropper thread is rather
+    /// blocked until the future completes, as with a closure sent with [`defer`](Self::defer),
+    /// and doesn't run it in an async runtime: a future needing one (tokio sockets or timers,
+    /// ...) must be sent to a dropper whose consumer is a task of that runtime.
+    ///
+    /// The future completes before the next [`flush`](Self::flush) does. A future which cannot
+    /// be sent (see [`OverflowPolicy`]) is run to completion in the current thread, and those left
+    /// when a consumer task is dropped with its executor are dropped without completing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// struct Connection {
+    ///     closed: Arc<AtomicBool>,
+    /// }
+    ///
+    /// impl Connection {
+    ///     async fn close(self) {
+    ///         self.closed.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let dropper = Dropper::<Vec<u8>>::new();
+    /// let closed = Arc::new(AtomicBool::new(false));
+    /// let connection = Connection { closed: Arc::clone(&closed) };
+    /// dropper.defer_async(connection.close());
+    /// dropper.flush();
+    /// assert!(closed.load(Ordering::SeqCst));
+    /// ```
+    #[inline]
+    pub fn defer_async(&self, cleanup: impl Future<Output = ()> + Send + 'static) {
+        self.0.defer_async(cleanup)
+    }
+
+    /// Wait for all values sent before this call to be dropped.
+    ///
+    /// Values sent concurrently from other threads may or may not be waited for.
+    /// Unlike dropping the last `Dropper` clone, the dropper is still usable afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn flush(&self) {
+        self.0.flush()
+    }
+
+    /// Asynchronously wait for all values sent before this call to be dropped.
+    ///
+    /// This is the same as [`flush`](Self::flush), without blocking the current thread while the
+    /// values are dropped, so an async service can await its cleanup from a task. It works with
+    /// any async runtime, and waits for room in the queue if it is bounded and full.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let dropper = Dropper::new();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush_async().await;
+    /// assert_eq!(dropper.pending(), 0);
+    /// # }
+    /// ```
+    #[inline]
+    pub async fn flush_async(&self) {
+        self.0.flush_async().await
+    }
+
+    /// Wait at most `timeout` for all values sent before this call to be dropped.
+    ///
+    /// Returns `true` if all values have been dropped, `false` if the timeout expired first.
+    #[inline]
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.0.flush_timeout(timeout)
+    }
+
+    /// Get a stream of the drops done by the dropper threads, for observability.
+    ///
+    /// Each value (or batch of values) dropped after this call is reported with its type and
+    /// the time spent dropping it. The drops are only timed while a stream exists.
+    ///
+    /// Available with the `futures` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use futures::StreamExt;
+    /// # futures::executor::block_on(async {
+    /// let dropper = Dropper::new();
+    /// let mut events = dropper.events();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// let event = events.next().await.unwrap();
+    /// assert_eq!(event.type_name, std::any::type_name::<Vec<u8>>());
+    /// assert_eq!(event.count, 1);
+    /// # });
+    /// ```
+    #[cfg(feature = "futures")]
+    pub fn events(&self) -> DropEvents {
+        let (events, observer) = DropEvents::new();
+        self.0.observe(observer);
+        events
+    }
+
+    /// Call `on_slow_drop` (in the dropper thread) for each drop taking at least `threshold`.
+    ///
+    /// The drop is reported with the type name of the values and the time spent dropping them:
+    /// this finds the values which would have stalled the calling threads if dropped there. As
+    /// with [`events`](Self::events), the drops are timed from this call. A value dropped a chunk
+    /// at a time (see [`dropout_chunked`](Self::dropout_chunked)) is reported with the time spent
+    /// on all its chunks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::time::Duration;
+    /// let dropper = Dropper::new();
+    /// dropper.on_slow_drop(Duration::from_millis(500), |event| {
+    ///     eprintln!(
+    ///         "Slow drop of {} value(s) of type {}: {:?}",
+    ///         event.count, event.type_name, event.duration
+    ///     );
+    /// });
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// ```
+    pub fn on_slow_drop(
+        &self,
+        threshold: Duration,
+        mut on_slow_drop: impl FnMut(&DropEvent) + Send + 'static,
+    ) {
+        self.0.observe(Box::new(move |event| {
+            if event.duration >= threshold {
+                on_slow_drop(event);
+            }
+            true
+        }));
+    }
+
+    /// Stop the dropper thread and wait for it to finish.
+    ///
+    /// All values sent before this call are dropped before the dropper thread stops.
+    /// Values sent afterwards through other handles (clones or [`WeakDropper`]) are dropped in
+    /// the sending thread.
+    ///
+    /// Unlike dropping the dropper, this reports if the dropper thread has panicked,
+    /// which happens if a dropped value panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, ShutdownError};
+    /// struct Bomb;
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("Boom");
+    ///     }
+    /// }
+    ///
+    /// let dropper = Dropper::new();
+    /// dropper.dropout(Bomb);
+    /// assert_eq!(dropper.shutdown(), Err(ShutdownError::WorkerPanicked));
+    /// ```
+    #[inline]
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.0.shutdown()
+    }
+
+    /// Stop the dropper thread and wait for it to finish, giving back the panic payload
+    /// if the dropper thread has panicked.
+    ///
+    /// This is the same as [`shutdown`](Self::shutdown), but the panic payload can be inspected
+    /// or propagated with [`std::panic::resume_unwind`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// struct Bomb;
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("Boom");
+    ///     }
+    /// }
+    ///
+    /// let dropper = Dropper::new();
+    /// dropper.dropout(Bomb);
+    /// let payload = dropper.close().unwrap_err();
+    /// assert_eq!(payload.downcast_ref::<&str>(), Some(&"Boom"));
+    /// ```
+    #[inline]
+    pub fn close(self) -> Result<(), Box<dyn Any + Send>> {
+        self.0.close()
+    }
+
+    /// Stop the dropper thread, waiting at most `timeout` for it to finish.
+    ///
+    /// This is the same as [`shutdown`](Self::shutdown), but if the dropper thread hasn't
+    /// finished dropping values before `timeout`, it is detached (see [`detach`](Self::detach))
+    /// and [`ShutdownOutcome::TimedOut`] is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, ShutdownOutcome};
+    /// # use std::time::Duration;
+    /// struct Slow;
+    /// impl Drop for Slow {
+    ///     fn drop(&mut self) {
+    ///         std::thread::sleep(Duration::from_secs(1));
+    ///     }
+    /// }
+    ///
+    /// let dropper = Dropper::new();
+    /// dropper.dropout(Slow);
+    /// let outcome = dropper.shutdown_timeout(Duration::from_millis(10));
+    /// assert_eq!(outcome, ShutdownOutcome::TimedOut);
+    /// ```
+    #[inline]
+    pub fn shutdown_timeout(self, timeout: Duration) -> ShutdownOutcome {
+        self.0.shutdown_timeout(timeout)
+    }
+
+    /// Stop the dropper thread once `token` is cancelled, waiting at most `timeout` for it to
+    /// finish.
+    ///
+    /// This is the async counterpart of [`shutdown_timeout`](Self::shutdown_timeout), to shut
+    /// down a dropper along with the rest of a service. Once `token` is cancelled, the dropper
+    /// thread drops the values already sent then stops, values sent after by other handles are
+    /// dropped in the sending thread. If it hasn't finished before `timeout`, it is detached
+    /// and [`ShutdownOutcome::TimedOut`] is returned.
+    ///
+    /// Available with the `tokio-util` feature, the timeout needs a tokio runtime with the time
+    /// driver enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, ShutdownOutcome};
+    /// # use std::time::Duration;
+    /// # use tokio_util::sync::CancellationToken;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let token = CancellationToken::new();
+    /// let dropper = Dropper::new();
+    /// let shutdown = tokio::spawn(dropper.clone().shutdown_on(token.clone(), Duration::from_secs(1)));
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// token.cancel();
+    /// assert_eq!(shutdown.await.unwrap(), ShutdownOutcome::Completed);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio-util")]
+    pub async fn shutdown_on(self, token: CancellationToken, timeout: Duration) -> ShutdownOutcome {
+        token.cancelled().await;
+        match tokio::time::timeout(timeout, self.0.shutdown_async()).await {
+            Ok(Ok(())) => ShutdownOutcome::Completed,
+            Ok(Err(ShutdownError::WorkerPanicked)) => ShutdownOutcome::WorkerPanicked,
+            Err(_) => {
+                self.0.detach();
+                ShutdownOutcome::TimedOut
+            }
+        }
+    }
+
+    /// Release this handle without waiting for the dropper thread to finish.
+    ///
+    /// The dropper thread is detached: when the last handle is dropped, it will not wait
+    /// for pending values to be dropped. If the process exits before, those values are never
+    /// dropped (this is intentional, exiting fast is the point of detaching).
+    ///
+    /// Other handles are still usable to send values.
+    #[inline]
+    pub fn detach(self) {
+        self.0.detach()
+    }
+
+    /// Wrap `value` into a [`Deferred`], sending it to this dropper when the wrapper is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper = Dropper::new();
+    /// let mut cache = dropper.wrap(HashMap::new());
+    /// cache.insert(1, vec![1; 1024]);
+    /// assert_eq!(cache.len(), 1);
+    /// // `cache` is dropped in the dropper thread at the end of the scope.
+    /// ```
+    #[inline]
+    pub fn wrap(&self, value: T) -> Deferred<T> {
+        Deferred::new(value, self.clone())
+    }
+
+    /// Share `value` in a [`DropArc`], sending it to this dropper when the last reference
+    /// is dropped.
+    #[inline]
+    pub fn wrap_arc(&self, value: T) -> DropArc<T> {
+        DropArc::new(value, self.clone())
+    }
+
+    /// Convert this dropper into an [`AnyDropper`] accepting values of any type.
+    ///
+    /// The `AnyDropper` uses the same dropper thread (and queue), no new thread is spawned.
+    /// Other handles on this dropper (clones) can still be used to send `T` values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{AnyDropper, Dropper};
+    /// let dropper = Dropper::<Vec<u8>>::with_capacity(1024);
+    /// let any_dropper: AnyDropper = dropper.clone().erase();
+    /// dropper.dropout(vec![0; 1024]);
+    /// any_dropper.dropout(String::from("Hello"));
+    /// ```
+    #[inline]
+    pub fn erase(self) -> AnyDropper {
+        AnyDropper::from(self)
+    }
+
+    /// Create a [`WeakDropper`] handle which doesn't keep the dropper thread alive.
+    #[inline]
+    pub fn downgrade(&self) -> WeakDropper<T> {
+        WeakDropper(Arc::downgrade(&self.0))
+    }
+
+    /// Wait until the queue has at least `slots` free slots, without blocking the current thread.
+    ///
+    /// This lets async producers reserve headroom before a burst of values, so sending them
+    /// doesn't wait (values sent concurrently by other producers may still fill the queue).
+    /// `slots` is capped to the capacity of the queue. The future completes at once if the
+    /// queue is unbounded, and if the dropper thread has stopped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # futures::executor::block_on(async {
+    /// let dropper = Dropper::with_capacity(16);
+    /// for burst in 0..10 {
+    ///     dropper.wait_for_capacity(8).await;
+    ///     for i in 0..8 {
+    ///         dropper.dropout(vec![burst * i; 1024]);
+    ///     }
+    /// }
+    /// # });
+    /// ```
+    #[inline]
+    pub async fn wait_for_capacity(&self, slots: usize) {
+        self.0.wait_for_capacity(slots).await
+    }
+
+    /// Block the current thread until less than `threshold` values are pending.
+    ///
+    /// This lets producers throttle themselves without bounding the queue.
+    /// `wait_until_below(1)` waits until all values have been dropped.
+    /// It also returns if the dropper thread has stopped (as pending values will never be dropped).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// for i in 0..100 {
+    ///     dropper.wait_until_below(10);
+    ///     dropper.dropout(vec![i; 1024]);
+    /// }
+    /// ```
+    #[inline]
+    pub fn wait_until_below(&self, threshold: usize) {
+        self.0.wait_until_below(threshold)
+    }
+
+    /// Get a future completing once the dropper thread has stopped, for supervision.
+    ///
+    /// The future resolves to [`ShutdownError::WorkerPanicked`] if the dropper thread has been
+    /// stopped by a panic (probably because a dropped value panicked), and to `Ok(())` once it
+    /// has stopped normally (the dropper being shut down or dropped). It doesn't keep the dropper
+    /// alive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, ShutdownError};
+    /// struct Bomb;
+    ///
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("boom");
+    ///     }
+    /// }
+    ///
+    /// # futures::executor::block_on(async {
+    /// let dropper = Dropper::new();
+    /// let closed = dropper.closed();
+    /// dropper.dropout(Bomb);
+    /// assert_eq!(closed.await, Err(ShutdownError::WorkerPanicked));
+    /// assert!(!dropper.is_healthy());
+    /// # });
+    /// ```
+    #[inline]
+    pub fn closed(&self) -> Closed {
+        self.0.closed()
+    }
+
+    /// Stop dropping values until [`resume`](Self::resume) is called.
+    ///
+    /// Values can still be sent: they are queued (the [`OverflowPolicy`] applies once a bounded
+    /// queue is full), then dropped once resumed. This defers the reclamation work out of
+    /// latency-critical periods. A value being dropped is not interrupted.
+    ///
+    /// Flushes wait for the dropper to be resumed. Shutting down or dropping the last handle of
+    /// the dropper resumes it, to drop the remaining values. To bound the time values are
+    /// deferred, see [`DropperBuilder::max_deferral`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// dropper.pause();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// assert!(dropper.is_paused());
+    /// assert_eq!(dropper.pending(), 1);
+    /// // After the burst, drop the values.
+    /// dropper.resume();
+    /// dropper.flush();
+    /// assert_eq!(dropper.pending(), 0);
+    /// ```
+    #[inline]
+    pub fn pause(&self) {
+        self.0.pause()
+    }
+
+    /// Drop the values again after a [`pause`](Self::pause).
+    #[inline]
+    pub fn resume(&self) {
+        self.0.resume()
+    }
+
+    /// Returns `true` if the dropper is paused. See [`pause`](Self::pause).
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.0.is_paused()
+    }
+
+    /// Returns `true` if the dropper thread is still running and consuming values.
+    ///
+    /// This returns `false` once the dropper thread has stopped, either because it has panicked
+    /// (probably because a dropped value panicked) or because the dropper has been shut down.
+    /// Values sent then are dropped in the sending thread.
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.0.is_healthy()
+    }
+
+    /// Number of values sent to this dropper and not yet dropped.
+    ///
+    /// This includes the values waiting in the queue and the value being dropped (if any).
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.0.pending()
+    }
+
+    /// A snapshot of the activity of the dropper: values sent, dropped, pending, dropped in the
+    /// sending thread, ...
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// let dropper = Dropper::new();
+    /// for _ in 0..10 {
+    ///     dropper.dropout(vec![0u8; 1024]);
+    /// }
+    /// dropper.flush();
+    /// let stats = dropper.stats();
+    /// assert_eq!(stats.enqueued, 10);
+    /// assert_eq!(stats.dropped, 10);
+    /// assert_eq!(stats.pending, 0);
+    /// assert!(stats.peak_pending >= 1);
+    /// ```
+    #[inline]
+    pub fn stats(&self) -> DropperStats {
+        self.0.stats()
+    }
+
+    /// A [`prometheus`](https://docs.rs/prometheus) collector exposing the
+    /// [`stats`](Self::stats) of the dropper. See [`DropperCollector`].
+    ///
+    /// Available with the `prometheus` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::{Dropper, DropperBuilder};
+    /// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+    ///     .name("cache-dropper")
+    ///     .build()
+    ///     .unwrap();
+    /// let registry = prometheus::Registry::new();
+    /// registry
+    ///     .register(Box::new(dropper.prometheus_collector()))
+    ///     .unwrap();
+    /// dropper.dropout(vec![0u8; 1024]);
+    /// dropper.flush();
+    /// let families = registry.gather();
+    /// assert!(families
+    ///     .iter()
+    ///     .any(|family| family.name() == "dropout_dropped_total"));
+    /// ```
+    #[cfg(feature = "prometheus")]
+    pub fn prometheus_collector(&self) -> DropperCollector {
+        DropperCollector::new(self.0.stats_source())
+    }
+
+    /// Number of values waiting in the queue for the dropper thread.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no value is waiting in the queue.
+    ///
+    /// The dropper thread may still be dropping a value, see [`pending`](Self::pending).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`Dropper`] of boxed values of any type.
+///
+/// Callers with many unrelated heavy types can use one `BoxDropper` instead of one `Dropper<T>`
+/// per type. See also [`AnyDropper`], which allows to get back values which cannot be sent.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::BoxDropper;
+/// # use std::collections::HashMap;
+/// let dropper = BoxDropper::new();
+/// dropper.dropout_box(vec![0u8; 1024]);
+/// dropper.dropout_box(HashMap::<u32, String>::new());
+/// dropper.dropout(Box::new(String::from("Hello")));
+/// ```
+pub type BoxDropper = Dropper<Box<dyn Send>>;
+
+impl Dropper<Box<dyn Send>> {
+    /// Box a value and send it to be dropped in another thread. See [`dropout`](Self::dropout).
+    #[inline]
+    pub fn dropout_box<U: Send + 'static>(&self, to_drop: U) {
+        self.dropout(Box::new(to_drop))
+    }
+}
+
+impl<K, V, S> Dropper<HashMap<K, V, S>>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    S: BuildHasher + Clone + Send + 'static,
+{
+    /// Move all the entries of `map` to be dropped in another thread, leaving it empty.
+    ///
+    /// The entries are sent in one message, with the allocation of the map. `map` is left
+    /// empty, with the same hasher, and can be reused.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dropout::Dropper;
+    /// # use std::collections::HashMap;
+    /// let dropper = Dropper::new();
+    /// let mut cache: HashMap<u32, Vec<u8>> = (0..100).map(|i| (i, vec![0; 1024])).collect();
+    /// dropper.dropout_drain_map(&mut cache);
+    /// assert!(cache.is_empty());
+    /// cache.insert(1, vec![1; 1024]);
+    /// ```
+    #[inline]
+    pub fn dropout_drain_map(&self, map: &mut HashMap<K, V, S>) {
+        let empty = HashMap::with_hasher(map.hasher().clone());
+        self.dropout(std::mem::replace(map, empty))
+    }
+}
+
+/// What to do when a value is sent to a [`Dropper`] whose queue is full.
+///
+/// This only applies to [`Dropper::dropout`]. The other sending methods
+/// ([`try_dropout`](Dropper::try_dropout), [`dropout_blocking`](Dropper::dropout_blocking), ...)
+/// have an explicit behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block the current thread until there is room in the queue.
+    Block,
+    /// Drop the value in the current thread.
+    #[default]
+    DropInline,
+    /// Refuse the value: it is dropped in the current thread and counted in
+    /// [`DropperStats::rejected`].
+    ///
+    /// Use this if a full queue is a bug in your application, to report it. To get the value
+    /// back instead, send it with [`try_dropout`](Dropper::try_dropout).
+    Reject,
+}
+
+/// Priority of a value sent with [`Dropper::dropout_with_priority`].
+///
+/// The dropper thread drops the values of higher priority first: small but urgent releases
+/// (file handles, locks, ...) are not delayed by the bulk of the values. Values of the same
+/// priority are dropped in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Dropped before the other values.
+    High,
+    /// The priority of the values sent with [`Dropper::dropout`].
+    #[default]
+    Normal,
+    /// Dropped once there is no other value to drop.
+    Low,
+}
+
+/// Order in which the dropper thread drops the values sent with the same priority.
+///
+/// Set with [`DropperBuilder::order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropOrder {
+    /// Drop the values in the order they were sent.
+    #[default]
+    Fifo,
+    /// Drop the most recently sent values first.
+    ///
+    /// The dropper thread takes all the queued values at once, then drops them from the newest to
+    /// the oldest. Recent values are more likely to be still in the CPU caches, and are released
+    /// sooner. Flushes still wait for all the values sent before them.
+    Lifo,
+}
+
+/// How the dropper thread pauses between drops, to limit its share of a CPU core.
+///
+/// Set with [`DropperBuilder::throttle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    /// Sleep for `pause` every time `values` values have been dropped.
+    Every {
+        /// Number of values dropped between two pauses.
+        values: usize,
+        /// Duration of each pause.
+        pause: Duration,
+    },
+    /// Keep the dropper thread busy at most this percentage of the time, sleeping after each
+    /// drop in proportion to its duration.
+    DutyCycle(u8),
+}
+
+/// Scheduling priority of the dropper threads, so they don't compete with the other threads of
+/// the process for the CPU.
+///
+/// Set with [`DropperBuilder::thread_priority`] or [`ThreadSpawner::priority`]. Setting the
+/// priority is best-effort: if the system refuses it, the threads keep the default priority.
+/// It is supported on Linux, Android (where nice values and `SCHED_IDLE` are per thread),
+/// Windows (where they are mapped to the thread priorities) and Apple systems (where they are
+/// mapped to QoS classes): on the other systems, the priority is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Lower the priority to this nice value, from 0 (the default priority) to 19 (the lowest).
+    ///
+    /// On Windows, nice values from 1 to 9 are `THREAD_PRIORITY_BELOW_NORMAL`, and from 10 to 19
+    /// `THREAD_PRIORITY_LOWEST`. On Apple systems, they are the `QOS_CLASS_UTILITY` QoS class.
+    Nice(u8),
+    /// Run only when no other thread wants the CPU: `SCHED_IDLE` (or nice 19 if the system
+    /// doesn't support it), `THREAD_PRIORITY_IDLE` on Windows, the `QOS_CLASS_BACKGROUND` QoS
+    /// class on Apple systems.
+    Idle,
+    /// Run in background mode: on Windows, `THREAD_MODE_BACKGROUND_BEGIN` also lowers the
+    /// priority of the memory and I/O accesses of the thread (or `THREAD_PRIORITY_LOWEST` if the
+    /// system refuses it). On Apple systems, the `QOS_CLASS_BACKGROUND` QoS class runs the thread
+    /// on the efficiency cores, with throttled I/O. Same as [`Idle`](Self::Idle) on the other
+    /// systems.
+    Background,
+    /// The `QOS_CLASS_UTILITY` QoS class on Apple systems, for long-running work whose progress
+    /// the user doesn't follow, balancing speed and energy efficiency. Same as `Nice(10)` on the
+    /// other systems.
+    Utility,
+}
+
+/// What to do when the dropper thread panics, probably because a dropped value panicked.
+///
+/// Set with [`DropperBuilder::panic_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catch the panic and continue dropping values in the same thread.
+    ///
+    /// The panic is not reported.
+    Ignore,
+    /// Replace the panicking dropper thread by a new one.
+    ///
+    /// The panic is not reported.
+    Restart,
+    /// Let the dropper thread stop.
+    ///
+    /// Values sent afterward are dropped in the sending thread and the panic is reported by
+    /// [`Dropper::shutdown`] and [`Dropper::close`].
+    #[default]
+    Propagate,
+    /// Abort the process.
+    Abort,
+}
+
+/// Callbacks called when the number of pending values of a [`Dropper`] crosses watermarks.
+///
+/// `on_high` is called (in the sending thread) when the pending count reaches the high watermark.
+/// `on_low` is then called (in the dropper thread) when the pending count falls back to the low
+/// watermark. Calls alternate: `on_high` is not called again before `on_low` has been called.
+pub struct Watermarks {
+    high: usize,
+    low: usize,
+    on_high: Option<Box<dyn Fn() + Send + Sync>>,
+    on_low: Option<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl Watermarks {
+    /// Create watermarks with no callback.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is not lower than `high`.
+    pub fn new(high: usize, low: usize) -> Self {
+        assert!(
+            low < high,
+            "Low watermark must be lower than high watermark"
+        );
+        Self {
+            high,
+            low,
+            on_high: None,
+            on_low: None,
         }
     }
+
+    /// Set the callback called when the high watermark is reached.
+    pub fn on_high(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_high = Some(Box::new(f));
+        self
+    }
+
+    /// Set the callback called when the low watermark is reached.
+    pub fn on_low(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_low = Some(Box::new(f));
+        self
+    }
+}
+
+impl std::fmt::Debug for Watermarks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watermarks")
+            .field("high", &self.high)
+            .field("low", &self.low)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + 'static> Default for Dropper<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> Clone for Dropper<T> {
+    fn clone(&self) -> Self {
+        Self::from_inner(Arc::clone(&self.0))
+    }
 }
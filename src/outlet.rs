@@ -0,0 +1,133 @@
+use crate::{AnyDropper, Dropper, DropperPool, ShardedPool, WeakDropper};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Something values can be sent to, to be dropped.
+///
+/// Libraries can accept any `DropOutlet` instead of a [`Dropper`], so their users choose where
+/// values are dropped, and tests can substitute a deterministic outlet: [`InlineDrop`] drops
+/// the values right away and a `Mutex<Vec<T>>` keeps them to be inspected.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{DropOutlet, Dropper, InlineDrop};
+/// # use std::sync::Mutex;
+/// fn replace_cache(cache: &mut Vec<String>, outlet: &impl DropOutlet<Vec<String>>) {
+///     let old = std::mem::take(cache);
+///     outlet.dropout(old);
+/// }
+///
+/// let mut cache = vec![String::from("a"), String::from("b")];
+/// replace_cache(&mut cache, &Dropper::new());
+/// replace_cache(&mut cache, &InlineDrop);
+///
+/// // In tests
+/// let mut cache = vec![String::from("a")];
+/// let recorder = Mutex::new(Vec::new());
+/// replace_cache(&mut cache, &recorder);
+/// assert_eq!(recorder.into_inner().unwrap(), vec![vec![String::from("a")]]);
+/// ```
+pub trait DropOutlet<T> {
+    /// Take ownership of `to_drop` to drop it.
+    fn dropout(&self, to_drop: T);
+}
+
+/// A [`DropOutlet`] dropping values in the current thread, right away.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InlineDrop;
+
+impl<T> DropOutlet<T> for InlineDrop {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        drop(to_drop)
+    }
+}
+
+impl<T: Send + 'static> DropOutlet<T> for Dropper<T> {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        Dropper::dropout(self, to_drop)
+    }
+}
+
+impl<T: Send + 'static> DropOutlet<T> for WeakDropper<T> {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        WeakDropper::dropout(self, to_drop)
+    }
+}
+
+impl<T: Send + 'static> DropOutlet<T> for AnyDropper {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        AnyDropper::dropout(self, to_drop)
+    }
+}
+
+impl<T: Send + 'static> DropOutlet<T> for DropperPool {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        DropperPool::dropout(self, to_drop)
+    }
+}
+
+impl<T: Send + 'static> DropOutlet<T> for ShardedPool {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        ShardedPool::dropout(self, to_drop)
+    }
+}
+
+/// Keep the values instead of dropping them, to inspect them in tests.
+impl<T> DropOutlet<T> for Mutex<Vec<T>> {
+    fn dropout(&self, to_drop: T) {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(to_drop)
+    }
+}
+
+impl<T, O: DropOutlet<T> + ?Sized> DropOutlet<T> for &O {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        (**self).dropout(to_drop)
+    }
+}
+
+impl<T, O: DropOutlet<T> + ?Sized> DropOutlet<T> for Arc<O> {
+    #[inline]
+    fn dropout(&self, to_drop: T) {
+        (**self).dropout(to_drop)
+    }
+}
+
+/// Extension trait to send values to a [`DropOutlet`] fluently.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, DropoutExt};
+/// # use std::collections::HashMap;
+/// let dropper = Dropper::new();
+/// let maps: Vec<HashMap<u32, Vec<u8>>> = vec![HashMap::new(), HashMap::new()];
+/// let sizes: Vec<usize> = maps
+///     .into_iter()
+///     .map(|map| {
+///         let len = map.len();
+///         map.dropout_to(&dropper);
+///         len
+///     })
+///     .collect();
+/// assert_eq!(sizes, vec![0, 0]);
+/// ```
+pub trait DropoutExt: Sized {
+    /// Send `self` to `outlet` to be dropped.
+    fn dropout_to<O: DropOutlet<Self> + ?Sized>(self, outlet: &O);
+}
+
+impl<T> DropoutExt for T {
+    #[inline]
+    fn dropout_to<O: DropOutlet<Self> + ?Sized>(self, outlet: &O) {
+        outlet.dropout(self)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::inner::{self, Core, ErasedDropper};
+use crate::{DropperBuilder, DropperStats, ShutdownError};
+use std::any;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A pool of dropper threads, all consuming the same queue.
+///
+/// Use a pool when one dropper thread cannot keep up with the values to drop.
+/// Like an [`AnyDropper`](crate::AnyDropper), a pool accepts values of any type.
+///
+/// Values are dropped concurrently by the threads of the pool: there is no guarantee about
+/// the order in which they are dropped, even for values sent from a single thread.
+///
+/// Typed droppers using the threads of a pool are created with
+/// [`Dropper::spawn_on`](crate::Dropper::spawn_on).
+///
+/// # Slow values
+///
+/// The threads of the pool take the values from the shared queue one at a time, when they are
+/// idle. So a thread stuck dropping a huge value doesn't hold back the values queued after it:
+/// they are taken by the other threads. See [`ShardedPool`](crate::ShardedPool) for a pool with
+/// one queue per thread.
+///
+/// ```
+/// # use dropout::DropperPool;
+/// # use std::sync::mpsc::{self, Receiver};
+/// /// Blocks its dropper thread until released.
+/// struct Huge(Receiver<()>);
+/// impl Drop for Huge {
+///     fn drop(&mut self) {
+///         let _ = self.0.recv();
+///     }
+/// }
+///
+/// let pool = DropperPool::new(2);
+/// let (release, released) = mpsc::channel();
+/// pool.dropout(Huge(released));
+/// for i in 0..100 {
+///     pool.dropout(vec![i; 1024]);
+/// }
+/// // The values queued after `Huge` are dropped while it is still being dropped.
+/// while pool.pending() > 1 {
+///     std::thread::yield_now();
+/// }
+/// release.send(()).unwrap();
+/// pool.flush();
+/// assert_eq!(pool.pending(), 0);
+/// ```
+///
+/// # Example
+///
+/// ```
+/// # use dropout::DropperPool;
+/// # use std::collections::HashMap;
+/// let pool = DropperPool::new(4);
+/// for i in 0..100 {
+///     let map: HashMap<usize, Vec<usize>> = (0..1000).map(|v| (v, vec![v + i])).collect();
+///     pool.dropout(map);
+/// }
+/// pool.flush();
+/// assert_eq!(pool.pending(), 0);
+/// ```
+pub struct DropperPool(pub(crate) Arc<Core>);
+
+impl DropperPool {
+    /// Create a new DropperPool with `workers` threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0 or if the dropper threads cannot be created.
+    /// See [`try_new`](Self::try_new).
+    #[inline]
+    pub fn new(workers: usize) -> Self {
+        Self::try_new(workers).expect("Should succeed to create threads")
+    }
+
+    /// Create a new DropperPool with `workers` threads, returning an error if the dropper threads
+    /// cannot be created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0.
+    #[inline]
+    pub fn try_new(workers: usize) -> io::Result<Self> {
+        Self::with_builder(DropperBuilder::new(), workers)
+    }
+
+    /// Create a new DropperPool with `workers` threads configured by `builder`.
+    ///
+    /// All the threads of the pool have the name set in `builder`. The queue capacity
+    /// is shared by all the threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0.
+    pub fn with_builder(builder: DropperBuilder, workers: usize) -> io::Result<Self> {
+        inner::Dropper::new(builder.workers(workers)).map(|d| Self(Arc::new(d)))
+    }
+
+    /// Number of dropper threads of the pool.
+    #[inline]
+    pub fn workers(&self) -> usize {
+        self.0.workers()
+    }
+
+    /// Send a value to be dropped by one of the threads of the pool.
+    ///
+    /// See [`Dropper::dropout`](crate::Dropper::dropout).
+    #[inline]
+    pub fn dropout<U: Send + 'static>(&self, to_drop: U) {
+        self.0
+            .dropout_erased(Box::new(to_drop), any::type_name::<U>())
+    }
+
+    /// Send a closure to be run by one of the threads of the pool.
+    /// See [`Dropper::defer`](crate::Dropper::defer).
+    #[inline]
+    pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+        let (job, type_name) = inner::job(f);
+        self.0.dropout_erased(job, type_name)
+    }
+
+    /// Try to send a value to be dropped by one of the threads of the pool,
+    /// giving it back on failure.
+    ///
+    /// See [`Dropper::try_dropout`](crate::Dropper::try_dropout).
+    #[inline]
+    pub fn try_dropout<U: Send + 'static>(&self, to_drop: U) -> Result<(), U> {
+        self.0
+            .try_dropout_erased(Box::new(to_drop), any::type_name::<U>())
+            .map_err(|to_drop| *to_drop.downcast().unwrap())
+    }
+
+    /// Wait for all values sent before this call to be dropped.
+    /// See [`Dropper::flush`](crate::Dropper::flush).
+    ///
+    /// Each thread of the pool waits for the others to have dropped their values, so this can be
+    /// slowed down by a slow drop.
+    #[inline]
+    pub fn flush(&self) {
+        self.0.flush()
+    }
+
+    /// Wait at most `timeout` for all values sent before this call to be dropped.
+    /// See [`Dropper::flush_timeout`](crate::Dropper::flush_timeout).
+    #[inline]
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.0.flush_timeout(timeout)
+    }
+
+    /// Number of values sent to the pool and not yet dropped.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.0.pending()
+    }
+
+    /// A snapshot of the activity of the threads of the pool.
+    /// See [`Dropper::stats`](crate::Dropper::stats).
+    #[inline]
+    pub fn stats(&self) -> DropperStats {
+        self.0.stats()
+    }
+
+    /// Returns `true` if at least one thread of the pool is still running.
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.0.is_healthy()
+    }
+
+    /// Stop the threads of the pool and wait for them to finish.
+    /// See [`Dropper::shutdown`](crate::Dropper::shutdown).
+    #[inline]
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.0.shutdown()
+    }
+}
+
+impl Clone for DropperPool {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
@@ -0,0 +1,123 @@
+use crate::ThreadPriority;
+
+impl ThreadPriority {
+    /// Panic if the priority is invalid.
+    pub(crate) fn check(self) {
+        if let Self::Nice(nice) = self {
+            assert!(nice <= 19, "nice value must be at most 19");
+        }
+    }
+
+    /// Set the priority of the current thread, leaving it unchanged on failure.
+    pub(crate) fn apply(self) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        linux::apply(self);
+        #[cfg(windows)]
+        windows::apply(self);
+        #[cfg(target_vendor = "apple")]
+        apple::apply(self);
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux {
+    use crate::ThreadPriority;
+    use std::os::raw::{c_int, c_uint};
+
+    const PRIO_PROCESS: c_int = 0;
+    const SCHED_IDLE: c_int = 5;
+
+    #[repr(C)]
+    struct SchedParam {
+        sched_priority: c_int,
+    }
+
+    extern "C" {
+        fn setpriority(which: c_int, who: c_uint, prio: c_int) -> c_int;
+        fn sched_setscheduler(pid: c_int, policy: c_int, param: *const SchedParam) -> c_int;
+    }
+
+    // On Linux, the id 0 designates the calling thread, not the whole process.
+    pub(super) fn apply(priority: ThreadPriority) {
+        match priority {
+            ThreadPriority::Nice(nice) => set_nice(nice),
+            ThreadPriority::Utility => set_nice(10),
+            ThreadPriority::Idle | ThreadPriority::Background => {
+                let param = SchedParam { sched_priority: 0 };
+                // SAFETY: `param` is a valid `sched_param`, only read during the call.
+                if unsafe { sched_setscheduler(0, SCHED_IDLE, &param) } != 0 {
+                    set_nice(19);
+                }
+            }
+        }
+    }
+
+    fn set_nice(nice: u8) {
+        // SAFETY: `setpriority` has no memory safety requirement.
+        unsafe {
+            setpriority(PRIO_PROCESS, 0, c_int::from(nice));
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use crate::ThreadPriority;
+    use std::ffi::c_void;
+    use std::os::raw::c_int;
+
+    const THREAD_MODE_BACKGROUND_BEGIN: c_int = 0x0001_0000;
+    const THREAD_PRIORITY_BELOW_NORMAL: c_int = -1;
+    const THREAD_PRIORITY_LOWEST: c_int = -2;
+    const THREAD_PRIORITY_IDLE: c_int = -15;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadPriority(thread: *mut c_void, priority: c_int) -> c_int;
+    }
+
+    pub(super) fn apply(priority: ThreadPriority) {
+        // Tried in order until one is accepted.
+        let priorities: &[c_int] = match priority {
+            ThreadPriority::Nice(0) => return,
+            ThreadPriority::Nice(1..=9) => &[THREAD_PRIORITY_BELOW_NORMAL],
+            ThreadPriority::Nice(_) | ThreadPriority::Utility => &[THREAD_PRIORITY_LOWEST],
+            ThreadPriority::Idle => &[THREAD_PRIORITY_IDLE],
+            ThreadPriority::Background => &[THREAD_MODE_BACKGROUND_BEGIN, THREAD_PRIORITY_LOWEST],
+        };
+        // SAFETY: the pseudo handle of the current thread is always valid.
+        let thread = unsafe { GetCurrentThread() };
+        for &priority in priorities {
+            // SAFETY: `thread` is a valid thread handle.
+            if unsafe { SetThreadPriority(thread, priority) } != 0 {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+mod apple {
+    use crate::ThreadPriority;
+    use std::os::raw::{c_int, c_uint};
+
+    const QOS_CLASS_UTILITY: c_uint = 0x11;
+    const QOS_CLASS_BACKGROUND: c_uint = 0x09;
+
+    extern "C" {
+        fn pthread_set_qos_class_self_np(qos_class: c_uint, relative_priority: c_int) -> c_int;
+    }
+
+    pub(super) fn apply(priority: ThreadPriority) {
+        let qos_class = match priority {
+            ThreadPriority::Nice(0) => return,
+            ThreadPriority::Nice(_) | ThreadPriority::Utility => QOS_CLASS_UTILITY,
+            ThreadPriority::Idle | ThreadPriority::Background => QOS_CLASS_BACKGROUND,
+        };
+        // SAFETY: `pthread_set_qos_class_self_np` has no memory safety requirement.
+        unsafe {
+            pthread_set_qos_class_self_np(qos_class, 0);
+        }
+    }
+}
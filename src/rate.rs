@@ -0,0 +1,103 @@
+use crate::{DropperBuilder, Throttle};
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Paces the drops of a dropper, to a maximum number of values and bytes per second and to a
+/// share of the CPU time.
+///
+/// Each handled message postpones the next one by the time its values take at the maximum rate,
+/// so the drops are spread evenly instead of happening in bursts, and by the pause required by
+/// the throttle.
+pub(crate) struct Pacer {
+    drops_per_second: Option<u64>,
+    bytes_per_second: Option<u64>,
+    throttle: Option<Throttle>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// When the next message can be handled.
+    next: Option<Instant>,
+    /// Number of values dropped since the last pause of [`Throttle::Every`].
+    dropped: usize,
+}
+
+impl Pacer {
+    /// Create the pacer configured by `config`, `None` if the drops are not paced.
+    pub(crate) fn new(config: &DropperBuilder) -> Option<Self> {
+        if config.max_drops_per_second.is_none()
+            && config.max_bytes_per_second.is_none()
+            && config.throttle.is_none()
+        {
+            return None;
+        }
+        Some(Self {
+            drops_per_second: config.max_drops_per_second,
+            bytes_per_second: config.max_bytes_per_second,
+            throttle: config.throttle,
+            state: Mutex::default(),
+        })
+    }
+
+    /// When the next message can be handled, `None` if it can be handled now.
+    pub(crate) fn throttled(&self) -> Option<Instant> {
+        let next = self
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .next;
+        next.filter(|next| *next > Instant::now())
+    }
+
+    /// Account for the drop of `count` values of `bytes` bytes, started at `started`, postponing
+    /// the next message.
+    pub(crate) fn charge(&self, count: usize, bytes: usize, started: Instant) {
+        let delay = |amount: usize, rate: Option<u64>| {
+            rate.map_or(Duration::ZERO, |rate| {
+                Duration::from_secs_f64(amount as f64 / rate as f64)
+            })
+        };
+        let delay = delay(count, self.drops_per_second).max(delay(bytes, self.bytes_per_second));
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        // Time not used while idle is not saved for a later burst.
+        let start = state.next.map_or(started, |next| next.max(started));
+        let mut next = start + delay;
+        let pause = match self.throttle {
+            Some(Throttle::Every { values, pause }) => {
+                state.dropped += count;
+                match state.dropped >= values {
+                    true => {
+                        state.dropped %= values;
+                        pause
+                    }
+                    false => Duration::ZERO,
+                }
+            }
+            Some(Throttle::DutyCycle(percent)) => {
+                let busy = now - started;
+                busy.mul_f64(f64::from(100 - percent) / f64::from(percent))
+            }
+            None => Duration::ZERO,
+        };
+        next = next.max(now + pause);
+        state.next = Some(next);
+    }
+}
+
+/// Checks whether the system is idle, for droppers dropping values only then.
+pub(crate) struct IdleProbe {
+    /// Time between two checks while the system is busy.
+    pub(crate) interval: Duration,
+    pub(crate) is_idle: Box<dyn Fn() -> bool + Send + Sync>,
+}
+
+impl fmt::Debug for IdleProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleProbe")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
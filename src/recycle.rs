@@ -0,0 +1,175 @@
+use crate::Dropper;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A collection which can be cleared and reused. See [`Recycler`].
+pub trait Recycle: Send + 'static {
+    /// Remove all the elements, keeping the allocated memory.
+    fn clear(&mut self);
+
+    /// Number of elements the collection can hold without reallocating.
+    fn capacity(&self) -> usize;
+
+    /// Create an empty collection able to hold `capacity` elements.
+    fn with_capacity(capacity: usize) -> Self;
+}
+
+impl<T: Send + 'static> Recycle for Vec<T> {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+}
+
+impl<T: Send + 'static> Recycle for VecDeque<T> {
+    fn clear(&mut self) {
+        VecDeque::clear(self)
+    }
+
+    fn capacity(&self) -> usize {
+        VecDeque::capacity(self)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        VecDeque::with_capacity(capacity)
+    }
+}
+
+impl Recycle for String {
+    fn clear(&mut self) {
+        String::clear(self)
+    }
+
+    fn capacity(&self) -> usize {
+        String::capacity(self)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        String::with_capacity(capacity)
+    }
+}
+
+impl<K, V, S> Recycle for HashMap<K, V, S>
+where
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+    S: BuildHasher + Default + Send + 'static,
+{
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+
+    fn capacity(&self) -> usize {
+        HashMap::capacity(self)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        HashMap::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+impl<T, S> Recycle for HashSet<T, S>
+where
+    T: Eq + Hash + Send + 'static,
+    S: BuildHasher + Default + Send + 'static,
+{
+    fn clear(&mut self) {
+        HashSet::clear(self)
+    }
+
+    fn capacity(&self) -> usize {
+        HashSet::capacity(self)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        HashSet::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+/// Trade full collections for empty ones, clearing them in a dropper thread.
+///
+/// [`recycle`](Self::recycle) sends a collection to the dropper thread and gives an empty one
+/// back right away. The dropper thread clears the collection (dropping its elements) and keeps
+/// it in a pool, so the next call gets an already allocated collection: this avoids both the drop
+/// latency and the reallocation cost of the next fill.
+///
+/// If the pool is empty, a new collection is allocated with the capacity of the recycled one.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, Recycler};
+/// # use std::collections::HashMap;
+/// let recycler = Recycler::new(Dropper::new());
+/// let mut map: HashMap<usize, Vec<u8>> = HashMap::new();
+/// for round in 0..10 {
+///     for i in 0..1000 {
+///         map.insert(i, vec![round; 16]);
+///     }
+///     map = recycler.recycle(map);
+///     assert!(map.is_empty());
+///     assert!(map.capacity() >= 1000);
+/// }
+/// ```
+pub struct Recycler<C: Recycle> {
+    dropper: Dropper<C>,
+    pool: Arc<Mutex<Vec<C>>>,
+    max_pooled: usize,
+}
+
+impl<C: Recycle> Recycler<C> {
+    /// Create a recycler clearing collections in the thread of `dropper`.
+    ///
+    /// At most 4 cleared collections are kept, see [`with_max_pooled`](Self::with_max_pooled).
+    #[inline]
+    pub fn new(dropper: Dropper<C>) -> Self {
+        Self::with_max_pooled(dropper, 4)
+    }
+
+    /// Create a recycler keeping at most `max_pooled` cleared collections.
+    ///
+    /// Collections recycled while the pool is full are dropped.
+    pub fn with_max_pooled(dropper: Dropper<C>, max_pooled: usize) -> Self {
+        Self {
+            dropper,
+            pool: Arc::new(Mutex::new(Vec::with_capacity(max_pooled))),
+            max_pooled,
+        }
+    }
+
+    /// Send `collection` to be cleared in the dropper thread and get an empty one.
+    pub fn recycle(&self, mut collection: C) -> C {
+        let capacity = collection.capacity();
+        let recycled = self
+            .pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let pool = Arc::clone(&self.pool);
+        let max_pooled = self.max_pooled;
+        self.dropper.defer(move || {
+            collection.clear();
+            let mut pool = pool.lock().unwrap_or_else(PoisonError::into_inner);
+            if pool.len() < max_pooled {
+                pool.push(collection);
+            }
+        });
+        recycled.unwrap_or_else(|| C::with_capacity(capacity))
+    }
+
+    /// Number of cleared collections ready to be reused.
+    pub fn pooled(&self) -> usize {
+        self.pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
@@ -0,0 +1,129 @@
+use crate::inner::{self, Core};
+use crate::{Dropper, DropperBuilder, DropperStats, ShutdownError};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// A set of typed [`Dropper`]s all sharing one dropper thread.
+///
+/// Instead of one thread per `Dropper<T>`, [`dropper::<T>()`](Self::dropper) hands out a
+/// `Dropper<T>` sending its values to the registry's thread. There is one `Dropper<T>` per type
+/// (the same one is returned each time), values being boxed to be sent to the shared thread.
+///
+/// Values sent through the droppers of a registry are dropped in the order they are received,
+/// whatever their type. [`pending`](Dropper::pending) and [`flush`](Dropper::flush) on one of
+/// these droppers apply to the whole thread. Shutting one of them down only waits for the values
+/// already sent: the thread is stopped when the registry and all its droppers are dropped
+/// (or with [`DropperRegistry::shutdown`]).
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, DropperRegistry};
+/// # use std::collections::HashMap;
+/// let registry = DropperRegistry::new();
+/// let vec_dropper: Dropper<Vec<u8>> = registry.dropper();
+/// let map_dropper: Dropper<HashMap<u32, String>> = registry.dropper();
+/// vec_dropper.dropout(vec![0; 1024]);
+/// map_dropper.dropout(HashMap::new());
+/// registry.flush();
+/// assert_eq!(vec_dropper.pending(), 0);
+/// ```
+pub struct DropperRegistry {
+    core: Arc<Core>,
+    droppers: Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl DropperRegistry {
+    /// Create a new DropperRegistry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dropper thread cannot be created. See [`try_new`](Self::try_new).
+    #[inline]
+    pub fn new() -> Self {
+        Self::try_new().expect("Should succeed to create thread")
+    }
+
+    /// Create a new DropperRegistry, returning an error if the dropper thread cannot be created.
+    #[inline]
+    pub fn try_new() -> io::Result<Self> {
+        Self::with_builder(DropperBuilder::new())
+    }
+
+    /// Create a new DropperRegistry whose thread is configured by `builder`.
+    pub fn with_builder(builder: DropperBuilder) -> io::Result<Self> {
+        Ok(Self {
+            core: Arc::new(inner::Dropper::new(builder)?),
+            droppers: Mutex::default(),
+        })
+    }
+
+    /// Get the dropper of `T` values, sending them to the registry's thread.
+    pub fn dropper<T: Send + 'static>(&self) -> Dropper<T> {
+        let mut droppers = self.droppers.lock().unwrap_or_else(PoisonError::into_inner);
+        droppers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Dropper::<T>::from_inner(Arc::new(
+                    inner::Dropper::attached(&self.core),
+                )))
+            })
+            .downcast_ref::<Dropper<T>>()
+            .unwrap()
+            .clone()
+    }
+
+    /// Wait for all values sent before this call to be dropped. See [`Dropper::flush`].
+    #[inline]
+    pub fn flush(&self) {
+        self.core.flush()
+    }
+
+    /// Wait at most `timeout` for all values sent before this call to be dropped.
+    /// See [`Dropper::flush_timeout`].
+    #[inline]
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.core.flush_timeout(timeout)
+    }
+
+    /// Returns `true` if the current thread is the dropper thread of the registry.
+    pub(crate) fn is_dropper_thread(&self) -> bool {
+        self.core.is_current_thread()
+    }
+
+    /// Number of values sent to the droppers of this registry and not yet dropped.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.core.pending()
+    }
+
+    /// A snapshot of the activity of the dropper thread. See [`Dropper::stats`].
+    #[inline]
+    pub fn stats(&self) -> DropperStats {
+        self.core.stats()
+    }
+
+    /// Returns `true` if the dropper thread is still running. See [`Dropper::is_healthy`].
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.core.is_healthy()
+    }
+
+    /// Stop the dropper thread and wait for it to finish. See [`Dropper::shutdown`].
+    ///
+    /// Values sent afterwards through the droppers of this registry are dropped in the
+    /// sending thread.
+    #[inline]
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.core.shutdown()
+    }
+}
+
+impl Default for DropperRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
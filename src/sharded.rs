@@ -0,0 +1,144 @@
+use crate::inner::{self, Core, ErasedDropper};
+use crate::{DropperBuilder, ShutdownError};
+use std::any;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+thread_local! {
+    /// Hash of the current thread id, used to choose its shard.
+    static THREAD_HASH: u64 = {
+        let mut hasher = DefaultHasher::new();
+        thread::current().id().hash(&mut hasher);
+        hasher.finish()
+    };
+}
+
+/// A pool of dropper threads, each one with its own queue.
+///
+/// Unlike a [`DropperPool`](crate::DropperPool), where all the threads consume one queue,
+/// each producing thread always sends its values to the same dropper thread (chosen from a hash
+/// of the producer thread id). This reduces the contention on the queue when many threads send
+/// values at once, and values sent from a single thread are dropped in order.
+///
+/// The other side is that a thread stuck dropping a huge value holds back the values queued
+/// after it, even if other threads of the pool are idle.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::ShardedPool;
+/// let pool = ShardedPool::new(4);
+/// std::thread::scope(|s| {
+///     for _ in 0..8 {
+///         s.spawn(|| {
+///             for i in 0..1000 {
+///                 pool.dropout(vec![i; 16]);
+///             }
+///         });
+///     }
+/// });
+/// pool.flush();
+/// assert_eq!(pool.pending(), 0);
+/// ```
+pub struct ShardedPool(Arc<[Core]>);
+
+impl ShardedPool {
+    /// Create a new ShardedPool with `shards` threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0 or if the dropper threads cannot be created.
+    /// See [`try_new`](Self::try_new).
+    #[inline]
+    pub fn new(shards: usize) -> Self {
+        Self::try_new(shards).expect("Should succeed to create threads")
+    }
+
+    /// Create a new ShardedPool with `shards` threads, returning an error if the dropper threads
+    /// cannot be created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    pub fn try_new(shards: usize) -> io::Result<Self> {
+        assert!(shards > 0, "A ShardedPool needs at least one shard");
+        let cores = (0..shards)
+            .map(|_| inner::Dropper::new(DropperBuilder::new()))
+            .collect::<io::Result<_>>()?;
+        Ok(Self(cores))
+    }
+
+    /// Number of dropper threads of the pool.
+    #[inline]
+    pub fn shards(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The shard of the current thread.
+    #[inline]
+    fn shard(&self) -> &Core {
+        let hash = THREAD_HASH.with(|hash| *hash);
+        &self.0[(hash % self.0.len() as u64) as usize]
+    }
+
+    /// Send a value to be dropped by the dropper thread of the current thread.
+    ///
+    /// See [`Dropper::dropout`](crate::Dropper::dropout).
+    #[inline]
+    pub fn dropout<U: Send + 'static>(&self, to_drop: U) {
+        self.shard()
+            .dropout_erased(Box::new(to_drop), any::type_name::<U>())
+    }
+
+    /// Send a closure to be run by the dropper thread of the current thread.
+    /// See [`Dropper::defer`](crate::Dropper::defer).
+    #[inline]
+    pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+        let (job, type_name) = inner::job(f);
+        self.shard().dropout_erased(job, type_name)
+    }
+
+    /// Try to send a value to be dropped by the dropper thread of the current thread,
+    /// giving it back on failure.
+    ///
+    /// See [`Dropper::try_dropout`](crate::Dropper::try_dropout).
+    #[inline]
+    pub fn try_dropout<U: Send + 'static>(&self, to_drop: U) -> Result<(), U> {
+        self.shard()
+            .try_dropout_erased(Box::new(to_drop), any::type_name::<U>())
+            .map_err(|to_drop| *to_drop.downcast().unwrap())
+    }
+
+    /// Wait for all values sent before this call to be dropped, by all the threads
+    /// of the pool. See [`Dropper::flush`](crate::Dropper::flush).
+    pub fn flush(&self) {
+        self.0.iter().for_each(Core::flush)
+    }
+
+    /// Number of values sent to the pool and not yet dropped.
+    pub fn pending(&self) -> usize {
+        self.0.iter().map(Core::pending).sum()
+    }
+
+    /// Returns `true` if all the threads of the pool are still running.
+    pub fn is_healthy(&self) -> bool {
+        self.0.iter().all(Core::is_healthy)
+    }
+
+    /// Stop the threads of the pool and wait for them to finish.
+    /// See [`Dropper::shutdown`](crate::Dropper::shutdown).
+    ///
+    /// All the threads are stopped, the error of the first panicking one is returned.
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.0.iter().map(Core::shutdown).fold(Ok(()), Result::and)
+    }
+}
+
+impl Clone for ShardedPool {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::fmt;
+
+/// Error returned by [`Dropper::shutdown`](crate::Dropper::shutdown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ShutdownError {
+    /// The dropper thread has panicked (probably because a dropped value panicked).
+    ///
+    /// Some values may not have been dropped.
+    WorkerPanicked,
+}
+
+impl fmt::Display for ShutdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WorkerPanicked => write!(f, "dropper thread has panicked"),
+        }
+    }
+}
+
+impl Error for ShutdownError {}
+
+/// Outcome of [`Dropper::shutdown_timeout`](crate::Dropper::shutdown_timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub enum ShutdownOutcome {
+    /// All values have been dropped and the dropper thread has stopped.
+    Completed,
+    /// The dropper thread has panicked. Some values may not have been dropped.
+    WorkerPanicked,
+    /// The dropper thread didn't stop in time and has been detached.
+    TimedOut,
+}
+
+impl ShutdownOutcome {
+    /// Returns `true` if the dropper thread has stopped normally.
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Completed)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::Dropper;
+use futures_sink::Sink;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+/// The item given to the sink of a `Dropper` handle, not yet sent.
+///
+/// The mutex is only there to keep `Dropper` `Sync`: the slot is accessed through `&mut`.
+pub(crate) struct Slot<T>(Mutex<Option<T>>);
+
+// The item is never pinned.
+impl<T> Unpin for Slot<T> {}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+impl<T: Send + 'static> Dropper<T> {
+    /// Send the item of the slot, if any, `Pending` while the queue is full.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        let slot = self.1 .0.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(item) = slot.take() {
+            if let Err(item) = self.0.poll_dropout(cx, item) {
+                *slot = Some(item);
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A `Dropper` is a sink dropping the items sent to it in the dropper thread.
+///
+/// Each handle holds at most one item: the sink is ready once the previous item has been sent,
+/// pending while the queue is full, and `start_send` never blocks. Flushing or closing the sink
+/// sends the item held, it doesn't wait for the items to be dropped (see
+/// [`Dropper::flush_async`]). The overflow policy doesn't apply.
+///
+/// Available with the `futures` feature.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// # use futures::{stream, StreamExt};
+/// # futures::executor::block_on(async {
+/// let dropper = Dropper::new();
+/// let processed = stream::iter((0..100).map(|i| Ok(vec![i; 1024])));
+/// processed.forward(dropper.clone()).await.unwrap();
+/// dropper.flush_async().await;
+/// assert_eq!(dropper.pending(), 0);
+/// # });
+/// ```
+impl<T: Send + 'static> Sink<T> for Dropper<T> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.get_mut().poll_send(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Infallible> {
+        let slot = self
+            .get_mut()
+            .1
+             .0
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        assert!(
+            slot.is_none(),
+            "poll_ready was not called before start_send"
+        );
+        *slot = Some(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.get_mut().poll_send(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.get_mut().poll_send(cx)
+    }
+}
+
+/// Same as the `Dropper` sink.
+#[cfg(feature = "tokio")]
+impl<T: Send + 'static> Sink<T> for crate::AsyncDropper<T> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Infallible> {
+        Pin::new(&mut self.get_mut().0).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_close(cx)
+    }
+}
+
+/// Same as the `Dropper` sink.
+#[cfg(feature = "async-std")]
+impl<T: Send + 'static> Sink<T> for crate::AsyncStdDropper<T> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Infallible> {
+        Pin::new(&mut self.get_mut().0).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Pin::new(&mut self.get_mut().0).poll_close(cx)
+    }
+}
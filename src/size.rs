@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::mem;
+
+/// A value knowing (approximately) how much memory it releases when dropped.
+///
+/// Used by [`Dropper::dropout_measured`](crate::Dropper::dropout_measured) to count the bytes
+/// pending and dropped (see [`DropperStats`](crate::DropperStats)) and to apply
+/// [`DropperBuilder::max_bytes_per_second`](crate::DropperBuilder::max_bytes_per_second).
+///
+/// The implementations for the std collections count the memory allocated for their elements,
+/// not the memory the elements own themselves: computing it would take as long as dropping
+/// them. Implement it for your own types to count it.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::SizeHint;
+/// struct Image {
+///     pixels: Vec<u32>,
+///     thumbnails: Vec<Vec<u32>>,
+/// }
+///
+/// impl SizeHint for Image {
+///     fn size_hint(&self) -> usize {
+///         self.pixels.size_hint()
+///             + self.thumbnails.size_hint()
+///             + self.thumbnails.iter().map(SizeHint::size_hint).sum::<usize>()
+///     }
+/// }
+/// ```
+pub trait SizeHint {
+    /// Approximate number of bytes released when the value is dropped.
+    fn size_hint(&self) -> usize;
+}
+
+impl<T> SizeHint for Vec<T> {
+    fn size_hint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl<T> SizeHint for VecDeque<T> {
+    fn size_hint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl<T> SizeHint for LinkedList<T> {
+    fn size_hint(&self) -> usize {
+        // Each node holds two pointers besides the element.
+        self.len() * (mem::size_of::<T>() + 2 * mem::size_of::<usize>())
+    }
+}
+
+impl<T> SizeHint for BinaryHeap<T> {
+    fn size_hint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl SizeHint for String {
+    fn size_hint(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<K, V, S> SizeHint for HashMap<K, V, S> {
+    fn size_hint(&self) -> usize {
+        self.capacity() * mem::size_of::<(K, V)>()
+    }
+}
+
+impl<T, S> SizeHint for HashSet<T, S> {
+    fn size_hint(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl<K, V> SizeHint for BTreeMap<K, V> {
+    fn size_hint(&self) -> usize {
+        self.len() * mem::size_of::<(K, V)>()
+    }
+}
+
+impl<T> SizeHint for BTreeSet<T> {
+    fn size_hint(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+}
+
+impl<T: SizeHint + ?Sized> SizeHint for Box<T> {
+    fn size_hint(&self) -> usize {
+        mem::size_of_val(&**self) + (**self).size_hint()
+    }
+}
+
+// The memory of the elements of slices and strings is counted by the `Box` holding them.
+impl<T> SizeHint for [T] {
+    fn size_hint(&self) -> usize {
+        0
+    }
+}
+
+impl SizeHint for str {
+    fn size_hint(&self) -> usize {
+        0
+    }
+}
@@ -0,0 +1,254 @@
+use crate::affinity::Affinity;
+use crate::inner::Consumer;
+use crate::ThreadPriority;
+use std::io;
+use std::thread;
+
+/// Run the consumers of a dropper, decoupling the dropper from the way they are run.
+///
+/// A consumer drops the values sent to the dropper until it is closed. It can be run as a future
+/// (by an async executor) or with [`Consumer::run`] (blocking the current thread).
+/// A dropper created with [`DropperBuilder::build_with`](crate::DropperBuilder::build_with)
+/// spawns one consumer per [`worker`](crate::DropperBuilder::workers).
+///
+/// Implemented by [`ThreadSpawner`], `TokioSpawner` (with the `tokio` feature),
+/// `AsyncStdSpawner` (with the `async-std` feature), `FuturesSpawner` (with the `futures`
+/// feature) and closures.
+///
+/// Only the threads of a [`ThreadSpawner`] are joined when the last handle of the dropper is
+/// dropped. The consumers run by other spawners, including closures spawning threads, are not
+/// waited for: they may still be dropping the remaining values after that. Use
+/// [`Dropper::shutdown`](crate::Dropper::shutdown) to wait for them.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, DropperBuilder};
+/// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+///     .build_with(|consumer: dropout::Consumer| {
+///         std::thread::spawn(move || consumer.run());
+///         Ok(())
+///     })
+///     .unwrap();
+/// dropper.dropout(vec![0u8; 1024]);
+/// dropper.flush();
+/// ```
+pub trait Spawner {
+    /// Spawn `consumer`, running it until it completes.
+    fn spawn(&self, consumer: Consumer) -> io::Result<()>;
+}
+
+impl<F: Fn(Consumer) -> io::Result<()>> Spawner for F {
+    fn spawn(&self, consumer: Consumer) -> io::Result<()> {
+        self(consumer)
+    }
+}
+
+/// Run each consumer in a dedicated thread.
+///
+/// As with a dropper built with [`DropperBuilder::build`](crate::DropperBuilder::build), the
+/// threads are joined when the last handle of the dropper is dropped.
+#[derive(Clone, Debug)]
+pub struct ThreadSpawner {
+    name: String,
+    stack_size: Option<usize>,
+    priority: Option<ThreadPriority>,
+    affinity: Option<Affinity>,
+}
+
+impl ThreadSpawner {
+    /// Create a spawner of threads named "Dropout".
+    pub fn new() -> Self {
+        Self {
+            name: "Dropout".into(),
+            stack_size: None,
+            priority: None,
+            affinity: None,
+        }
+    }
+
+    /// Set the name of the threads.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the stack size of the threads.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Set the scheduling priority of the threads, see
+    /// [`DropperBuilder::thread_priority`](crate::DropperBuilder::thread_priority).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the nice value of [`ThreadPriority::Nice`] is greater than 19.
+    pub fn priority(mut self, priority: ThreadPriority) -> Self {
+        priority.check();
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Pin the threads to `cores`, see
+    /// [`DropperBuilder::core_affinity`](crate::DropperBuilder::core_affinity).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cores` is empty.
+    pub fn core_affinity(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        let cores: Vec<usize> = cores.into_iter().collect();
+        assert!(!cores.is_empty(), "cores must not be empty");
+        self.affinity = Some(Affinity::Cores(cores));
+        self
+    }
+
+    /// Pin the threads to the cores of the NUMA node `node`, see
+    /// [`DropperBuilder::numa_node`](crate::DropperBuilder::numa_node).
+    pub fn numa_node(mut self, node: usize) -> Self {
+        self.affinity = Some(Affinity::NumaNode(node));
+        self
+    }
+}
+
+impl Default for ThreadSpawner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, consumer: Consumer) -> io::Result<()> {
+        let mut builder = thread::Builder::new().name(self.name.clone());
+        if let Some(size) = self.stack_size {
+            builder = builder.stack_size(size);
+        }
+        let priority = self.priority;
+        let affinity = self.affinity.clone();
+        consumer.spawn_thread(builder, move || {
+            if let Some(priority) = priority {
+                priority.apply();
+            }
+            if let Some(affinity) = affinity {
+                affinity.apply();
+            }
+        })
+    }
+}
+
+/// Run each consumer as a task of a tokio runtime.
+///
+/// Available with the `tokio` feature.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioSpawner {
+    handle: tokio::runtime::Handle,
+    blocking: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioSpawner {
+    /// Create a spawner of tasks on the runtime of `handle`.
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        Self {
+            handle,
+            blocking: false,
+        }
+    }
+
+    /// Create a spawner of tasks on the current runtime, returning an error if called outside of
+    /// a tokio runtime.
+    pub fn current() -> io::Result<Self> {
+        tokio::runtime::Handle::try_current()
+            .map(Self::new)
+            .map_err(io::Error::other)
+    }
+
+    /// Run the consumers on the blocking threads of the runtime
+    /// (see [`spawn_blocking`](tokio::task::spawn_blocking)).
+    pub fn blocking(mut self) -> Self {
+        self.blocking = true;
+        self
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, consumer: Consumer) -> io::Result<()> {
+        if self.blocking {
+            self.handle.spawn_blocking(move || consumer.run());
+        } else {
+            self.handle.spawn(consumer);
+        }
+        Ok(())
+    }
+}
+
+/// Run each consumer as a task of the async-std executor.
+///
+/// Available with the `async-std` feature.
+#[cfg(feature = "async-std")]
+#[derive(Clone, Debug, Default)]
+pub struct AsyncStdSpawner {
+    blocking: bool,
+}
+
+#[cfg(feature = "async-std")]
+impl AsyncStdSpawner {
+    /// Create a spawner of tasks on the async-std executor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the consumers on the blocking threads of async-std
+    /// (see [`spawn_blocking`](async_std::task::spawn_blocking)).
+    pub fn blocking(mut self) -> Self {
+        self.blocking = true;
+        self
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl Spawner for AsyncStdSpawner {
+    fn spawn(&self, consumer: Consumer) -> io::Result<()> {
+        if self.blocking {
+            async_std::task::spawn_blocking(move || consumer.run());
+        } else {
+            async_std::task::spawn(consumer);
+        }
+        Ok(())
+    }
+}
+
+/// Run each consumer on an executor implementing the `futures` [`Spawn`](futures_task::Spawn)
+/// trait.
+///
+/// This lets any executor compatible with `futures` (a `ThreadPool`, a `LocalSpawner` of a
+/// thread dedicated to drops, ...) run the consumers.
+///
+/// Available with the `futures` feature.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, DropperBuilder, FuturesSpawner};
+/// let pool = futures::executor::ThreadPool::new().unwrap();
+/// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+///     .build_with(FuturesSpawner(pool))
+///     .unwrap();
+/// dropper.dropout(vec![0u8; 1024]);
+/// dropper.flush();
+/// ```
+#[cfg(feature = "futures")]
+#[derive(Clone, Debug)]
+pub struct FuturesSpawner<S>(pub S);
+
+#[cfg(feature = "futures")]
+impl<S: futures_task::Spawn> Spawner for FuturesSpawner<S> {
+    fn spawn(&self, consumer: Consumer) -> io::Result<()> {
+        self.0
+            .spawn_obj(futures_task::FutureObj::new(Box::new(consumer)))
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,409 @@
+use crate::DropperBuilder;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A snapshot of the activity of a dropper, to size its capacity.
+///
+/// Returned by [`Dropper::stats`](crate::Dropper::stats). The counts are those of the dropper
+/// threads: for a dropper sharing its threads (see
+/// [`Dropper::spawn_on`](crate::Dropper::spawn_on)), they include the values of the other
+/// droppers.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DropperStats {
+    /// Number of values sent to the queue.
+    pub enqueued: u64,
+    /// Number of values dropped by the dropper threads.
+    pub dropped: u64,
+    /// Number of values sent and not yet dropped, see [`Dropper::pending`](crate::Dropper::pending).
+    pub pending: usize,
+    /// Highest number of values pending at once.
+    pub peak_pending: usize,
+    /// Number of values dropped in the sending thread, because the queue was full (with
+    /// [`OverflowPolicy::DropInline`](crate::OverflowPolicy::DropInline)) or the dropper threads
+    /// had stopped.
+    pub dropped_inline: u64,
+    /// Number of values refused because the queue was full, with
+    /// [`OverflowPolicy::Reject`](crate::OverflowPolicy::Reject). They are dropped in the
+    /// sending thread, but not counted in [`dropped_inline`](Self::dropped_inline).
+    pub rejected: u64,
+    /// Approximate number of bytes of the values sent and not yet dropped.
+    ///
+    /// Only the values sent with a size (see
+    /// [`Dropper::dropout_measured`](crate::Dropper::dropout_measured)) are counted.
+    pub bytes_pending: usize,
+    /// Approximate number of bytes of the values dropped by the dropper threads, see
+    /// [`bytes_pending`](Self::bytes_pending).
+    pub bytes_dropped: u64,
+    /// Time since the dropper threads were started.
+    pub uptime: Duration,
+    /// Durations of the drops, if recorded (see
+    /// [`DropperBuilder::latency_histogram`](crate::DropperBuilder::latency_histogram)).
+    pub latency: Option<LatencyHistogram>,
+    /// The slowest drops, from the slowest (see
+    /// [`DropperBuilder::track_slowest`](crate::DropperBuilder::track_slowest)). Empty if they
+    /// are not tracked.
+    pub slowest: Vec<SlowDrop>,
+}
+
+/// One of the slowest drops of a dropper, see [`DropperStats::slowest`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SlowDrop {
+    /// Name of the type of the dropped values, see [`std::any::type_name`].
+    pub type_name: &'static str,
+    /// Number of values dropped.
+    pub count: usize,
+    /// Time spent dropping the values.
+    pub duration: Duration,
+    /// When the drop ended.
+    pub at: SystemTime,
+}
+
+/// Histogram of the time spent dropping each value.
+///
+/// The values sent in one message (with [`Dropper::dropout_many`](crate::Dropper::dropout_many)
+/// for instance) are each counted with the average duration of their drop. A collection dropped a
+/// chunk at a time is counted as one value.
+///
+/// # Example
+///
+/// ```
+/// # use dropout::{Dropper, DropperBuilder};
+/// # use std::time::Duration;
+/// let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+///     .latency_histogram([Duration::from_micros(10), Duration::from_millis(1)])
+///     .build()
+///     .unwrap();
+/// for _ in 0..100 {
+///     dropper.dropout(vec![0u8; 1024]);
+/// }
+/// dropper.flush();
+/// let latency = dropper.stats().latency.unwrap();
+/// assert_eq!(latency.count(), 100);
+/// println!(
+///     "{:?} spent dropping values, 99% of them in less than {:?}",
+///     latency.total,
+///     latency.quantile(0.99)
+/// );
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct LatencyHistogram {
+    /// Upper bounds of the buckets, in increasing order.
+    pub bounds: Vec<Duration>,
+    /// Number of values per bucket: `counts[i]` values were dropped in at most `bounds[i]` (and
+    /// more than `bounds[i - 1]`). The last count, after the ones of the bounds, is the number of
+    /// values dropped in more than the last bound.
+    pub counts: Vec<u64>,
+    /// Total time spent dropping the values, which would otherwise have been spent in the
+    /// threads sending them.
+    pub total: Duration,
+}
+
+impl LatencyHistogram {
+    /// Number of values recorded.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of the duration of the drops of the fraction `quantile` (from 0 to 1) of the
+    /// values dropped the fastest.
+    ///
+    /// Returns `None` if no value has been recorded, or if the quantile falls in the last bucket,
+    /// which has no upper bound.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (quantile.clamp(0.0, 1.0) * count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.counts) {
+            seen += bucket;
+            if seen >= rank {
+                return Some(*bound);
+            }
+        }
+        None
+    }
+}
+
+/// The buckets behind a `LatencyHistogram`.
+pub(crate) struct Histogram {
+    bounds: Vec<Duration>,
+    /// One count per bound, and one for the values dropped in more than the last bound.
+    counts: Vec<AtomicU64>,
+    /// Total duration of the drops, in nanoseconds.
+    total: AtomicU64,
+}
+
+impl Histogram {
+    pub(crate) fn new(mut bounds: Vec<Duration>) -> Self {
+        bounds.sort();
+        bounds.dedup();
+        let counts = (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect();
+        Self {
+            bounds,
+            counts,
+            total: AtomicU64::new(0),
+        }
+    }
+
+    /// Record the drop of `count` values in `duration`.
+    pub(crate) fn record(&self, count: usize, duration: Duration) {
+        let per_value = duration / u32::try_from(count.max(1)).unwrap_or(u32::MAX);
+        let bucket = self.bounds.partition_point(|bound| *bound < per_value);
+        add(&self.counts[bucket], count);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            bounds: self.bounds.clone(),
+            counts: self
+                .counts
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            total: Duration::from_nanos(self.total.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// The slowest drops of a dropper.
+pub(crate) struct Slowest {
+    capacity: usize,
+    drops: Mutex<Vec<SlowDrop>>,
+    /// Duration of the fastest drop kept once full, in nanoseconds, to skip the faster ones
+    /// without locking.
+    threshold: AtomicU64,
+}
+
+impl Slowest {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            drops: Mutex::new(Vec::with_capacity(capacity)),
+            threshold: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a drop, keeping it if it is one of the slowest.
+    pub(crate) fn record(&self, type_name: &'static str, count: usize, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        if nanos <= self.threshold.load(Ordering::Relaxed) {
+            return;
+        }
+        let drop = SlowDrop {
+            type_name,
+            count,
+            duration,
+            at: SystemTime::now(),
+        };
+        let mut drops = self.drops.lock().unwrap_or_else(PoisonError::into_inner);
+        if drops.len() < self.capacity {
+            drops.push(drop);
+        } else if let Some(fastest) = drops.iter_mut().min_by_key(|drop| drop.duration) {
+            if fastest.duration < duration {
+                *fastest = drop;
+            }
+        }
+        if drops.len() == self.capacity {
+            let fastest = drops
+                .iter()
+                .map(|drop| drop.duration)
+                .min()
+                .unwrap_or_default();
+            let fastest = u64::try_from(fastest.as_nanos()).unwrap_or(u64::MAX);
+            self.threshold.store(fastest, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<SlowDrop> {
+        let mut drops = self
+            .drops
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        drops.sort_by_key(|drop| Reverse(drop.duration));
+        drops
+    }
+}
+
+/// The counters behind the stats of a dropper.
+pub(crate) struct Counters {
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+    peak_pending: AtomicUsize,
+    dropped_inline: AtomicU64,
+    rejected: AtomicU64,
+    bytes_pending: AtomicUsize,
+    bytes_dropped: AtomicU64,
+    latency: Option<Histogram>,
+    slowest: Option<Slowest>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Metrics>,
+    /// The name of the dropper, labelling its metrics.
+    #[cfg(feature = "prometheus")]
+    name: String,
+    started: Instant,
+}
+
+impl Counters {
+    pub(crate) fn new(config: &DropperBuilder) -> Self {
+        Self {
+            enqueued: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            peak_pending: AtomicUsize::new(0),
+            dropped_inline: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            bytes_pending: AtomicUsize::new(0),
+            bytes_dropped: AtomicU64::new(0),
+            latency: config.latency_buckets.clone().map(Histogram::new),
+            slowest: config.track_slowest.map(Slowest::new),
+            #[cfg(feature = "metrics")]
+            metrics: config.metrics.then(|| Metrics::new(&config.name)),
+            #[cfg(feature = "prometheus")]
+            name: config.name.clone(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The name of the dropper.
+    #[cfg(feature = "prometheus")]
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if the durations of the drops are recorded.
+    pub(crate) fn timed(&self) -> bool {
+        #[cfg(feature = "metrics")]
+        if self.metrics.is_some() {
+            return true;
+        }
+        self.latency.is_some() || self.slowest.is_some()
+    }
+
+    /// Count `count` values of `bytes` bytes sent to the queue, `pending` values being pending.
+    pub(crate) fn sent(&self, count: usize, bytes: usize, pending: usize) {
+        add(&self.enqueued, count);
+        self.bytes_pending.fetch_add(bytes, Ordering::Relaxed);
+        self.peak_pending.fetch_max(pending, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.enqueued.increment(count as u64);
+            metrics.pending.set(pending as f64);
+        }
+    }
+
+    /// Count `count` values dropped by the dropper threads, `pending` values being pending.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn dropped(&self, count: usize, pending: usize) {
+        add(&self.dropped, count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.dropped.increment(count as u64);
+            metrics.pending.set(pending as f64);
+        }
+    }
+
+    /// Count `bytes` bytes dropped by the dropper threads.
+    pub(crate) fn dropped_bytes(&self, bytes: usize) {
+        self.bytes_pending.fetch_sub(bytes, Ordering::Relaxed);
+        add(&self.bytes_dropped, bytes);
+    }
+
+    /// Count `count` values dropped in the sending thread.
+    pub(crate) fn dropped_inline(&self, count: usize) {
+        add(&self.dropped_inline, count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.dropped_inline.increment(count as u64);
+        }
+    }
+
+    /// Count `count` values refused because the queue was full.
+    pub(crate) fn rejected(&self, count: usize) {
+        add(&self.rejected, count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.rejected.increment(count as u64);
+        }
+    }
+
+    /// Record the drop of `count` values of `type_name` in `duration`.
+    pub(crate) fn record(&self, type_name: &'static str, count: usize, duration: Duration) {
+        if let Some(latency) = &self.latency {
+            latency.record(count, duration);
+        }
+        if let Some(slowest) = &self.slowest {
+            slowest.record(type_name, count, duration);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            let per_value = duration / u32::try_from(count.max(1)).unwrap_or(u32::MAX);
+            metrics
+                .duration
+                .record_many(per_value.as_secs_f64(), count.max(1));
+        }
+    }
+
+    pub(crate) fn snapshot(&self, pending: usize) -> DropperStats {
+        DropperStats {
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            pending,
+            peak_pending: self.peak_pending.load(Ordering::Relaxed),
+            dropped_inline: self.dropped_inline.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            bytes_pending: self.bytes_pending.load(Ordering::Relaxed),
+            bytes_dropped: self.bytes_dropped.load(Ordering::Relaxed),
+            uptime: self.started.elapsed(),
+            latency: self.latency.as_ref().map(Histogram::snapshot),
+            slowest: self
+                .slowest
+                .as_ref()
+                .map_or_else(Vec::new, Slowest::snapshot),
+        }
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new(&DropperBuilder::new())
+    }
+}
+
+fn add(counter: &AtomicU64, count: usize) {
+    counter.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// The metrics of a dropper, emitted through the `metrics` facade.
+#[cfg(feature = "metrics")]
+struct Metrics {
+    pending: metrics::Gauge,
+    enqueued: metrics::Counter,
+    dropped: metrics::Counter,
+    dropped_inline: metrics::Counter,
+    rejected: metrics::Counter,
+    duration: metrics::Histogram,
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    fn new(name: &str) -> Self {
+        let labels = [("dropper", name.to_owned())];
+        Self {
+            pending: metrics::gauge!("dropout_pending", &labels),
+            enqueued: metrics::counter!("dropout_enqueued_total", &labels),
+            dropped: metrics::counter!("dropout_dropped_total", &labels),
+            dropped_inline: metrics::counter!("dropout_dropped_inline_total", &labels),
+            rejected: metrics::counter!("dropout_rejected_total", &labels),
+            duration: metrics::histogram!("dropout_drop_duration_seconds", &labels),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::delay;
+use crate::inner::WeakSender;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A value waiting for its expiry, shared by its handles and the timer thread.
+struct Slot<T: Send + 'static> {
+    value: Option<T>,
+    /// When the value expires.
+    deadline: Instant,
+    /// Deadline of the timer job in charge of the value, older jobs are ignored.
+    scheduled: Instant,
+    dropper: WeakSender<T>,
+}
+
+fn lock<T: Send + 'static>(slot: &Mutex<Slot<T>>) -> MutexGuard<'_, Slot<T>> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Schedule the expiry check of the value of `slot` at `at`.
+fn schedule<T: Send + 'static>(slot: Arc<Mutex<Slot<T>>>, at: Instant) {
+    delay::schedule(at, move || {
+        let mut guard = lock(&slot);
+        if guard.scheduled != at || guard.value.is_none() {
+            return;
+        }
+        if guard.deadline > Instant::now() {
+            // Touched since: check again at the new deadline.
+            let deadline = guard.deadline;
+            guard.scheduled = deadline;
+            drop(guard);
+            schedule(slot, deadline);
+            return;
+        }
+        let value = guard.value.take();
+        let dropper = guard.dropper.clone();
+        drop(guard);
+        if let Some(value) = value {
+            dropper.dropout(value);
+        }
+    });
+}
+
+/// A handle to a value sent to a dropper once its time-to-live has elapsed.
+///
+/// The expiry can be postponed with [`touch`](Self::touch), or cancelled with
+/// [`cancel`](Self::cancel) to take the value back. Dropping the handle doesn't cancel the
+/// expiry. Values are held by the process-global timer thread, as with
+/// [`Dropper::dropout_after`](crate::Dropper::dropout_after).
+///
+/// Created with [`Dropper::expire_after`](crate::Dropper::expire_after).
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// # use std::time::Duration;
+/// let dropper = Dropper::new();
+/// let session = dropper.expire_after(vec![0u8; 1024], Duration::from_secs(60));
+/// // The session is still used: postpone its expiry.
+/// session.touch(Duration::from_secs(60));
+/// // Resurrect it.
+/// assert_eq!(session.cancel(), Some(vec![0u8; 1024]));
+/// ```
+pub struct ExpiryHandle<T: Send + 'static>(Arc<Mutex<Slot<T>>>);
+
+impl<T: Send + 'static> ExpiryHandle<T> {
+    pub(crate) fn new(value: T, ttl: Duration, dropper: WeakSender<T>) -> Self {
+        let deadline = Instant::now() + ttl;
+        let slot = Arc::new(Mutex::new(Slot {
+            value: Some(value),
+            deadline,
+            scheduled: deadline,
+            dropper,
+        }));
+        schedule(Arc::clone(&slot), deadline);
+        Self(slot)
+    }
+
+    /// Reset the time-to-live of the value to `ttl` from now.
+    ///
+    /// Does nothing if the value has already expired or been cancelled.
+    pub fn touch(&self, ttl: Duration) {
+        let deadline = Instant::now() + ttl;
+        let mut guard = lock(&self.0);
+        if guard.value.is_none() {
+            return;
+        }
+        guard.deadline = deadline;
+        // A later check finds the new deadline, an earlier one must be scheduled.
+        if deadline < guard.scheduled {
+            guard.scheduled = deadline;
+            drop(guard);
+            schedule(Arc::clone(&self.0), deadline);
+        }
+    }
+
+    /// Cancel the expiry and take the value back, `None` if it has already expired.
+    pub fn cancel(&self) -> Option<T> {
+        lock(&self.0).value.take()
+    }
+
+    /// Returns `true` if the value has expired (and been sent to the dropper) or been cancelled.
+    pub fn is_expired(&self) -> bool {
+        lock(&self.0).value.is_none()
+    }
+
+    /// When the value expires.
+    pub fn deadline(&self) -> Instant {
+        lock(&self.0).deadline
+    }
+}
+
+impl<T: Send + 'static> Clone for ExpiryHandle<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
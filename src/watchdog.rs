@@ -0,0 +1,147 @@
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+/// A drop taking longer than the deadline of a watchdog.
+///
+/// Reported to the callback given to [`DropperBuilder::watchdog`](crate::DropperBuilder::watchdog).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct StuckDrop {
+    /// Name of the type of the values being dropped, see [`std::any::type_name`].
+    pub type_name: &'static str,
+    /// Number of values being dropped.
+    pub count: usize,
+    /// Time spent dropping the values so far.
+    pub elapsed: Duration,
+}
+
+/// A drop in progress in a dropper thread.
+struct Running {
+    id: u64,
+    type_name: &'static str,
+    count: usize,
+    started: Instant,
+    /// Set once reported, to report each stuck drop once.
+    reported: bool,
+}
+
+#[derive(Default)]
+struct Drops {
+    running: Vec<Running>,
+    sequence: u64,
+}
+
+/// Watches the drops of a dropper, reporting the ones taking longer than a deadline.
+///
+/// The drops are checked by a thread of the watchdog, so that a slow `on_stuck` (or a busy timer
+/// thread) doesn't delay the other droppers.
+pub(crate) struct Watchdog {
+    deadline: Duration,
+    on_stuck: Box<dyn Fn(&StuckDrop) + Send + Sync>,
+    drops: Mutex<Drops>,
+    /// The thread checking the drops, woken up to stop when the watchdog is dropped.
+    thread: OnceLock<Thread>,
+}
+
+impl Watchdog {
+    pub(crate) fn new(deadline: Duration, on_stuck: Box<dyn Fn(&StuckDrop) + Send + Sync>) -> Self {
+        Self {
+            deadline,
+            on_stuck,
+            drops: Mutex::default(),
+            thread: OnceLock::new(),
+        }
+    }
+
+    /// Start checking the drops in a new thread, until the watchdog is dropped.
+    pub(crate) fn start(self) -> io::Result<Arc<Self>> {
+        // A stuck drop is reported at most half a deadline late.
+        let interval = (self.deadline / 2).max(Duration::from_millis(1));
+        let watchdog = Arc::new(self);
+        let weak = Arc::downgrade(&watchdog);
+        let thread_handle = thread::Builder::new()
+            .name("Dropout watchdog".into())
+            .spawn(move || run(weak, interval))?;
+        let _ = watchdog.thread.set(thread_handle.thread().clone());
+        Ok(watchdog)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Drops> {
+        self.drops.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Watch a drop until the returned guard is dropped.
+    pub(crate) fn watch(&self, type_name: &'static str, count: usize) -> WatchGuard<'_> {
+        let mut drops = self.lock();
+        let id = drops.sequence;
+        drops.sequence += 1;
+        drops.running.push(Running {
+            id,
+            type_name,
+            count,
+            started: Instant::now(),
+            reported: false,
+        });
+        WatchGuard(self, id)
+    }
+
+    /// Report the drops running for longer than the deadline.
+    fn check(&self) {
+        let now = Instant::now();
+        let mut stuck = Vec::new();
+        for running in &mut self.lock().running {
+            let elapsed = now - running.started;
+            if !running.reported && elapsed >= self.deadline {
+                running.reported = true;
+                stuck.push(StuckDrop {
+                    type_name: running.type_name,
+                    count: running.count,
+                    elapsed,
+                });
+            }
+        }
+        // The callback may take time (or abort), the drops must not wait for it.
+        stuck.iter().for_each(|stuck| (self.on_stuck)(stuck));
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.get() {
+            thread.unpark();
+        }
+    }
+}
+
+/// Check the drops of `watchdog` every `interval`, while it exists.
+fn run(watchdog: Weak<Watchdog>, interval: Duration) {
+    loop {
+        thread::park_timeout(interval);
+        let Some(watchdog) = watchdog.upgrade() else {
+            return;
+        };
+        // A panicking callback must not stop the checks.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| watchdog.check()));
+    }
+}
+
+/// Stops watching a drop once it is done (or has panicked).
+pub(crate) struct WatchGuard<'a>(&'a Watchdog, u64);
+
+impl Drop for WatchGuard<'_> {
+    fn drop(&mut self) {
+        self.0.lock().running.retain(|running| running.id != self.1);
+    }
+}
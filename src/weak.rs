@@ -0,0 +1,63 @@
+use crate::{inner, Dropper};
+use std::sync::Weak;
+
+/// A weak handle to a [`Dropper`].
+///
+/// A `WeakDropper` can send values to the dropper thread as long as a [`Dropper`] handle
+/// exists, but it does not keep the dropper thread alive: when the last `Dropper` is dropped,
+/// the dropper thread is stopped and the `WeakDropper` drops the values in the current thread.
+///
+/// Created with [`Dropper::downgrade`].
+///
+/// # Example
+///
+/// ```
+/// # use dropout::Dropper;
+/// let dropper = Dropper::new();
+/// let weak = dropper.downgrade();
+/// weak.dropout(vec![1, 2, 3]); // Dropped in the dropper thread.
+/// drop(dropper);
+/// assert!(weak.upgrade().is_none());
+/// weak.dropout(vec![1, 2, 3]); // Dropped in the current thread.
+/// ```
+pub struct WeakDropper<T: Send>(pub(crate) Weak<inner::Dropper<T>>);
+
+impl<T: Send + 'static> WeakDropper<T> {
+    /// Try to get a [`Dropper`] handle, returning `None` if all `Dropper`s are gone.
+    #[inline]
+    pub fn upgrade(&self) -> Option<Dropper<T>> {
+        self.0.upgrade().map(Dropper::from_inner)
+    }
+
+    /// Send a value to be dropped in the dropper thread.
+    ///
+    /// If all `Dropper`s are gone, `to_drop` is dropped in the current thread.
+    /// See [`Dropper::dropout`].
+    ///
+    /// Note that the dropper is temporarily upgraded while the value is sent. If the last
+    /// `Dropper` is dropped at the same time, this call will wait for the dropper thread to stop.
+    #[inline]
+    pub fn dropout(&self, to_drop: T) {
+        match self.upgrade() {
+            Some(dropper) => dropper.dropout(to_drop),
+            None => drop(to_drop),
+        }
+    }
+
+    /// Try to send a value to be dropped in the dropper thread.
+    ///
+    /// The value is given back if all `Dropper`s are gone or if [`Dropper::try_dropout`] fails.
+    #[inline]
+    pub fn try_dropout(&self, to_drop: T) -> Result<(), T> {
+        match self.upgrade() {
+            Some(dropper) => dropper.try_dropout(to_drop),
+            None => Err(to_drop),
+        }
+    }
+}
+
+impl<T: Send> Clone for WeakDropper<T> {
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0))
+    }
+}
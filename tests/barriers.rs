@@ -0,0 +1,113 @@
+use dropout::{DropOrder, Dropper, DropperPool, Priority, ShutdownOutcome};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A value taking a millisecond to drop.
+struct Slow(Arc<AtomicUsize>);
+
+impl Drop for Slow {
+    fn drop(&mut self) {
+        thread::sleep(Duration::from_millis(1));
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A thread sending a value to `dropper` every 500µs, faster than they are dropped.
+struct Producer {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Producer {
+    fn start(dropper: Dropper<Slow>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                let dropped = Arc::new(AtomicUsize::new(0));
+                while !stop.load(Ordering::SeqCst) {
+                    dropper.dropout(Slow(Arc::clone(&dropped)));
+                    thread::sleep(Duration::from_micros(500));
+                }
+            }
+        });
+        // Let the queue fill up.
+        thread::sleep(Duration::from_millis(50));
+        Self { stop, thread }
+    }
+
+    fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.join().unwrap();
+    }
+}
+
+/// Send values of each priority, then flush while the `Normal` queue is kept busy.
+fn flush_under_load(dropper: Dropper<Slow>) {
+    let producer = Producer::start(dropper.clone());
+    let dropped = Arc::new(AtomicUsize::new(0));
+    for priority in [Priority::High, Priority::Normal, Priority::Low] {
+        for _ in 0..10 {
+            dropper.dropout_with_priority(Slow(Arc::clone(&dropped)), priority);
+        }
+    }
+    let flushed = dropper.flush_timeout(Duration::from_secs(3));
+    producer.stop();
+    assert!(flushed);
+    assert_eq!(dropped.load(Ordering::SeqCst), 30);
+}
+
+#[test]
+fn flush_under_load_fifo() {
+    flush_under_load(Dropper::new());
+}
+
+#[test]
+fn flush_under_load_lifo() {
+    flush_under_load(
+        Dropper::<Slow>::builder()
+            .order(DropOrder::Lifo)
+            .build()
+            .unwrap(),
+    );
+}
+
+#[test]
+fn flush_under_load_with_workers() {
+    flush_under_load(Dropper::<Slow>::builder().workers(3).build().unwrap());
+}
+
+#[test]
+fn flush_under_load_of_a_lane() {
+    let pool = DropperPool::new(1);
+    let producer = Producer::start(Dropper::spawn_on_weighted(&pool, 4));
+    let dropper = Dropper::spawn_on(&pool);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        dropper.dropout_with_priority(Slow(Arc::clone(&dropped)), Priority::Low);
+    }
+    let flushed = pool.flush_timeout(Duration::from_secs(3));
+    producer.stop();
+    assert!(flushed);
+    assert_eq!(dropped.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn shutdown_under_load() {
+    let dropper = Dropper::new();
+    let producer = Producer::start(dropper.clone());
+    let dropped = Arc::new(AtomicUsize::new(0));
+    dropper.dropout_with_priority(Slow(Arc::clone(&dropped)), Priority::Low);
+    let shutdown = thread::spawn(move || dropper.shutdown_timeout(Duration::from_secs(10)));
+    // The values sent before the shutdown are dropped while the producer is still running.
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while dropped.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    // The values sent after are dropped until the dropper thread stops.
+    producer.stop();
+    assert_eq!(shutdown.join().unwrap(), ShutdownOutcome::Completed);
+}
@@ -0,0 +1,32 @@
+use dropout::Dropper;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Counts its drops.
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn unbounded_max_count() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let dropper = Dropper::new();
+    let mut buffered = dropper
+        .buffered()
+        .max_count(usize::MAX)
+        .max_size(3, |_: &Counted| 1);
+    for _ in 0..7 {
+        buffered.dropout(Counted(dropped.clone()));
+    }
+    // Two batches of 3 values were sent, the last value is still buffered.
+    assert_eq!(buffered.len(), 1);
+    dropper.flush();
+    assert_eq!(dropped.load(Ordering::SeqCst), 6);
+    buffered.flush();
+    dropper.flush();
+    assert_eq!(dropped.load(Ordering::SeqCst), 7);
+}
@@ -0,0 +1,72 @@
+use dropout::{Dropper, DropperBuilder};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Reports when it is dropped, once released.
+struct Blocker(Sender<Instant>, Receiver<()>);
+
+impl Drop for Blocker {
+    fn drop(&mut self) {
+        let _ = self.0.send(Instant::now());
+        let _ = self.1.recv();
+    }
+}
+
+/// Reports when it is dropped.
+struct Timed(Sender<Instant>);
+
+impl Drop for Timed {
+    fn drop(&mut self) {
+        let _ = self.0.send(Instant::now());
+    }
+}
+
+/// Counts its drops.
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn pause_until_resumed() {
+    let dropper = Dropper::new();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    dropper.pause();
+    for _ in 0..3 {
+        dropper.dropout(Counted(Arc::clone(&dropped)));
+    }
+    // Flushes wait for the dropper to be resumed.
+    assert!(!dropper.flush_timeout(Duration::from_millis(50)));
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    assert_eq!(dropper.pending(), 3);
+    dropper.resume();
+    dropper.flush();
+    assert_eq!(dropped.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn defer_again_after_max_deferral() {
+    let max_deferral = Duration::from_millis(100);
+    let dropper: Dropper<Box<dyn Send>> = DropperBuilder::new()
+        .max_deferral(max_deferral)
+        .build()
+        .unwrap();
+    dropper.pause();
+    let (dropping, dropped) = mpsc::channel();
+    let (release, blocked) = mpsc::channel();
+    let paused = Instant::now();
+    dropper.dropout(Box::new(Blocker(dropping.clone(), blocked)));
+    // Dropped once deferred for `max_deferral`.
+    assert!(dropped.recv().unwrap() >= paused + max_deferral);
+    // Queued while the deferred values are dropped: the queue is never empty.
+    dropper.dropout(Box::new(Timed(dropping)));
+    let released = Instant::now();
+    release.send(()).unwrap();
+    assert!(dropped.recv().unwrap() >= released + max_deferral);
+    dropper.flush();
+}
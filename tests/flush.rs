@@ -0,0 +1,59 @@
+use dropout::{Dropper, DropperBuilder};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Blocks the dropper thread in its drop, until released.
+struct Blocker {
+    started: Sender<()>,
+    release: Receiver<()>,
+}
+
+impl Drop for Blocker {
+    fn drop(&mut self) {
+        let _ = self.started.send(());
+        let _ = self.release.recv();
+    }
+}
+
+/// Run `f` in another thread, failing if it doesn't return within a few seconds.
+fn within_timeout(f: impl FnOnce() + Send + 'static) {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        f();
+        let _ = sender.send(());
+    });
+    receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("Timed out");
+}
+
+#[test]
+fn flush_async_with_a_full_queue() {
+    within_timeout(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let dropper: Dropper<Box<dyn Send>> =
+                DropperBuilder::new().capacity(1).build().unwrap();
+            let (started, started_receiver) = mpsc::channel();
+            let (release, release_receiver) = mpsc::channel();
+            dropper.dropout(Box::new(Blocker {
+                started,
+                release: release_receiver,
+            }));
+            started_receiver.recv().unwrap();
+            while dropper.try_dropout(Box::new(vec![0u8; 1024])).is_ok() {}
+            let flush = tokio::spawn({
+                let dropper = dropper.clone();
+                async move { dropper.flush_async().await }
+            });
+            // The flush waits for room in the queue without blocking the runtime.
+            tokio::task::yield_now().await;
+            release.send(()).unwrap();
+            flush.await.unwrap();
+            assert_eq!(dropper.pending(), 0);
+        });
+    });
+}
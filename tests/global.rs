@@ -0,0 +1,43 @@
+use std::env;
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Set in the child process running the exit.
+const CHILD: &str = "DROPOUT_TEST_EXIT_CHILD";
+
+/// Exits the process when dropped.
+struct ExitOnDrop;
+
+impl Drop for ExitOnDrop {
+    fn drop(&mut self) {
+        process::exit(3);
+    }
+}
+
+#[test]
+fn exit_from_the_global_dropper_thread() {
+    if env::var_os(CHILD).is_some() {
+        dropout::dropout(ExitOnDrop);
+        thread::sleep(Duration::from_secs(60));
+        unreachable!("The process should have exited");
+    }
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(["exit_from_the_global_dropper_thread", "--exact"])
+        .env(CHILD, "1")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() > Duration::from_secs(10) {
+            child.kill().unwrap();
+            panic!("The process didn't exit: the exit flush waits for the dropper thread");
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    assert_eq!(status.code(), Some(3));
+}
@@ -0,0 +1,87 @@
+use dropout::{DropOrder, Dropper, DropperBuilder, DropperPool, Priority};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Records its tag when dropped.
+struct Tagged(usize, Arc<Mutex<Vec<usize>>>);
+
+impl Drop for Tagged {
+    fn drop(&mut self) {
+        self.1.lock().unwrap().push(self.0);
+    }
+}
+
+/// Blocks the dropper thread in its drop until released, so the next values stay in the queue.
+struct Blocker(Sender<()>, Receiver<()>);
+
+impl Drop for Blocker {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+        let _ = self.1.recv();
+    }
+}
+
+/// Block the dropper thread, returning the sender releasing it.
+fn block(dropper: &Dropper<Box<dyn Send>>) -> Sender<()> {
+    let (blocking, blocked) = mpsc::channel();
+    let (release, released) = mpsc::channel();
+    dropper.dropout(Box::new(Blocker(blocking, released)));
+    blocked.recv().unwrap();
+    release
+}
+
+#[test]
+fn drop_by_priority() {
+    let dropper = Dropper::new();
+    let order = Arc::default();
+    let release = block(&dropper);
+    let priorities = [
+        Priority::Low,
+        Priority::Normal,
+        Priority::High,
+        Priority::Low,
+        Priority::High,
+    ];
+    for (tag, priority) in priorities.into_iter().enumerate() {
+        dropper.dropout_with_priority(Box::new(Tagged(tag, Arc::clone(&order))), priority);
+    }
+    release.send(()).unwrap();
+    dropper.flush();
+    assert_eq!(*order.lock().unwrap(), [2, 4, 1, 0, 3]);
+}
+
+#[test]
+fn drop_the_newest_first() {
+    let dropper = DropperBuilder::new()
+        .order(DropOrder::Lifo)
+        .build()
+        .unwrap();
+    let order = Arc::default();
+    let release = block(&dropper);
+    for tag in 0..5 {
+        dropper.dropout(Box::new(Tagged(tag, Arc::clone(&order))));
+    }
+    release.send(()).unwrap();
+    dropper.flush();
+    assert_eq!(*order.lock().unwrap(), [4, 3, 2, 1, 0]);
+}
+
+#[test]
+fn share_a_pool_by_weight() {
+    let pool = DropperPool::new(1);
+    let light = Dropper::spawn_on_weighted(&pool, 1);
+    let heavy = Dropper::spawn_on_weighted(&pool, 3);
+    let order = Arc::default();
+    let release = block(&Dropper::spawn_on(&pool));
+    for _ in 0..8 {
+        light.dropout(Box::new(Tagged(0, Arc::clone(&order))));
+        heavy.dropout(Box::new(Tagged(1, Arc::clone(&order))));
+    }
+    release.send(()).unwrap();
+    pool.flush();
+    let order = order.lock().unwrap();
+    // While both have values queued, the heavy dropper gets 3 drops for each of the light one.
+    let heavy_drops = order[..8].iter().filter(|&&tag| tag == 1).count();
+    assert_eq!(heavy_drops, 6);
+    assert_eq!(order.len(), 16);
+}
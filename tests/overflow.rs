@@ -0,0 +1,92 @@
+use dropout::{Dropper, DropperBuilder, OverflowPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+
+/// A value recording the threads it is dropped in.
+struct Tracked(Arc<AtomicUsize>, ThreadId);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if thread::current().id() == self.1 {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Fill the queue of a paused dropper with `policy`, then send 10 more values.
+///
+/// Returns the dropper, paused, and the number of the values dropped in the current thread.
+fn overflow(policy: OverflowPolicy) -> (Dropper<Tracked>, Arc<AtomicUsize>) {
+    let dropper: Dropper<Tracked> = DropperBuilder::new()
+        .capacity(4)
+        .overflow_policy(policy)
+        .build()
+        .unwrap();
+    dropper.pause();
+    let inline = Arc::new(AtomicUsize::new(0));
+    let current = thread::current().id();
+    while dropper
+        .try_dropout(Tracked(Arc::clone(&inline), current))
+        .is_ok()
+    {}
+    // The value given back by `try_dropout` has been dropped here.
+    inline.store(0, Ordering::SeqCst);
+    for _ in 0..10 {
+        dropper.dropout(Tracked(Arc::clone(&inline), current));
+    }
+    (dropper, inline)
+}
+
+#[test]
+fn drop_inline() {
+    let (dropper, inline) = overflow(OverflowPolicy::DropInline);
+    assert_eq!(inline.load(Ordering::SeqCst), 10);
+    let stats = dropper.stats();
+    assert_eq!(stats.dropped_inline, 10);
+    assert_eq!(stats.rejected, 0);
+}
+
+#[test]
+fn reject() {
+    let (dropper, inline) = overflow(OverflowPolicy::Reject);
+    assert_eq!(inline.load(Ordering::SeqCst), 10);
+    let stats = dropper.stats();
+    assert_eq!(stats.rejected, 10);
+    assert_eq!(stats.dropped_inline, 0);
+    dropper.resume();
+    dropper.flush();
+    assert_eq!(dropper.pending(), 0);
+}
+
+#[test]
+fn block() {
+    let dropper: Dropper<Tracked> = DropperBuilder::new()
+        .capacity(4)
+        .overflow_policy(OverflowPolicy::Block)
+        .build()
+        .unwrap();
+    dropper.pause();
+    let inline = Arc::new(AtomicUsize::new(0));
+    let producer = thread::spawn({
+        let dropper = dropper.clone();
+        let inline = Arc::clone(&inline);
+        move || {
+            let current = thread::current().id();
+            for _ in 0..20 {
+                dropper.dropout(Tracked(Arc::clone(&inline), current));
+            }
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    // The producer waits for room in the queue.
+    assert!(!producer.is_finished());
+    dropper.resume();
+    producer.join().unwrap();
+    dropper.flush();
+    assert_eq!(inline.load(Ordering::SeqCst), 0);
+    let stats = dropper.stats();
+    assert_eq!(stats.dropped, 20);
+    assert_eq!(stats.dropped_inline, 0);
+}
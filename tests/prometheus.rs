@@ -0,0 +1,93 @@
+#![cfg(feature = "prometheus")]
+
+use dropout::{Dropper, DropperBuilder, OverflowPolicy};
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::time::Duration;
+
+/// The text exposition of the metrics of `registry`.
+fn scrape(registry: &Registry) -> String {
+    let mut text = Vec::new();
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut text)
+        .unwrap();
+    String::from_utf8(text).unwrap()
+}
+
+#[test]
+fn stats_are_exposed_with_the_dropper_label() {
+    let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+        .name("cache")
+        .capacity(1)
+        .overflow_policy(OverflowPolicy::Reject)
+        .build()
+        .unwrap();
+    let registry = Registry::new();
+    registry
+        .register(Box::new(dropper.prometheus_collector()))
+        .unwrap();
+    dropper.pause();
+    while dropper.try_dropout(vec![0; 1024]).is_ok() {}
+    dropper.dropout(vec![0; 1024]);
+    let stats = dropper.stats();
+    let text = scrape(&registry);
+    assert!(text.contains(&format!(
+        "dropout_pending{{dropper=\"cache\"}} {}\n",
+        stats.pending
+    )));
+    assert!(text.contains(&format!(
+        "dropout_enqueued_total{{dropper=\"cache\"}} {}\n",
+        stats.enqueued
+    )));
+    assert!(text.contains("dropout_rejected_total{dropper=\"cache\"} 1\n"));
+    assert!(text.contains("dropout_dropped_total{dropper=\"cache\"} 0\n"));
+    // Not recorded without a latency histogram.
+    assert!(!text.contains("dropout_drop_duration_seconds"));
+    dropper.resume();
+    dropper.flush();
+    let text = scrape(&registry);
+    assert!(text.contains("dropout_pending{dropper=\"cache\"} 0\n"));
+    assert!(text.contains(&format!(
+        "dropout_dropped_total{{dropper=\"cache\"}} {}\n",
+        stats.enqueued
+    )));
+}
+
+#[test]
+fn latency_is_exposed_as_a_cumulative_histogram() {
+    let dropper: Dropper<Vec<u8>> = DropperBuilder::new()
+        .name("timed")
+        .latency_histogram([Duration::from_secs(10), Duration::from_secs(20)])
+        .build()
+        .unwrap();
+    let registry = Registry::new();
+    registry
+        .register(Box::new(dropper.prometheus_collector()))
+        .unwrap();
+    for _ in 0..3 {
+        dropper.dropout(vec![0; 1024]);
+    }
+    dropper.flush();
+    let text = scrape(&registry);
+    assert!(text.contains("dropout_drop_duration_seconds_bucket{dropper=\"timed\",le=\"10\"} 3\n"));
+    assert!(text.contains("dropout_drop_duration_seconds_bucket{dropper=\"timed\",le=\"20\"} 3\n"));
+    assert!(
+        text.contains("dropout_drop_duration_seconds_bucket{dropper=\"timed\",le=\"+Inf\"} 3\n")
+    );
+    assert!(text.contains("dropout_drop_duration_seconds_count{dropper=\"timed\"} 3\n"));
+}
+
+#[test]
+fn two_droppers_in_one_registry() {
+    let registry = Registry::new();
+    let first: Dropper<Vec<u8>> = DropperBuilder::new().name("first").build().unwrap();
+    let second: Dropper<Vec<u8>> = DropperBuilder::new().name("second").build().unwrap();
+    registry
+        .register(Box::new(first.prometheus_collector()))
+        .unwrap();
+    registry
+        .register(Box::new(second.prometheus_collector()))
+        .unwrap();
+    let text = scrape(&registry);
+    assert!(text.contains("dropout_pending{dropper=\"first\"} 0\n"));
+    assert!(text.contains("dropout_pending{dropper=\"second\"} 0\n"));
+}
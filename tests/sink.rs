@@ -0,0 +1,42 @@
+#![cfg(feature = "futures")]
+
+use dropout::{Dropper, DropperBuilder};
+use futures::task::noop_waker_ref;
+use futures::{Sink, SinkExt};
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+/// Run `f` in another thread, failing if it doesn't return within a few seconds.
+fn within_timeout(f: impl FnOnce() + Send + 'static) {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        f();
+        let _ = sender.send(());
+    });
+    receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("Timed out");
+}
+
+#[test]
+fn pending_while_the_queue_is_full() {
+    within_timeout(|| {
+        let mut dropper: Dropper<Vec<u8>> = DropperBuilder::new().capacity(2).build().unwrap();
+        dropper.pause();
+        while dropper.try_dropout(vec![0; 1024]).is_ok() {}
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut sink = Pin::new(&mut dropper);
+        // The handle holds the item, sending it doesn't wait for room in the queue.
+        assert!(sink.as_mut().poll_ready(&mut cx).is_ready());
+        sink.as_mut().start_send(vec![1; 1024]).unwrap();
+        assert_eq!(sink.as_mut().poll_ready(&mut cx), Poll::Pending);
+        assert_eq!(sink.as_mut().poll_flush(&mut cx), Poll::Pending);
+        dropper.resume();
+        futures::executor::block_on(SinkExt::flush(&mut dropper)).unwrap();
+        dropper.flush();
+        assert_eq!(dropper.pending(), 0);
+    });
+}
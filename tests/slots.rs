@@ -0,0 +1,30 @@
+use dropout::Dropper;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+/// Records the thread it is dropped in.
+#[derive(Default)]
+struct Tracked(Option<Arc<Mutex<Option<ThreadId>>>>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if let Some(dropped_in) = &self.0 {
+            *dropped_in.lock().unwrap() = Some(thread::current().id());
+        }
+    }
+}
+
+#[test]
+fn swap_drops_the_old_value_in_the_dropper_thread() {
+    let dropped_in = Arc::new(Mutex::new(None));
+    let dropper = Dropper::new();
+    let mut slot = Tracked(Some(Arc::clone(&dropped_in)));
+    dropper.dropout_swap(&mut slot);
+    assert!(slot.0.is_none());
+    dropper.flush();
+    let dropped_in = dropped_in
+        .lock()
+        .unwrap()
+        .expect("the old value was not dropped");
+    assert_ne!(dropped_in, thread::current().id());
+}
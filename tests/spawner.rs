@@ -0,0 +1,287 @@
+use dropout::{Dropper, DropperBuilder, ThreadSpawner};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Takes a while to drop.
+struct Sleep;
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Run `f` in another thread, failing if it doesn't return within a few seconds.
+fn within_timeout(f: impl FnOnce() + Send + 'static) {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        f();
+        let _ = sender.send(());
+    });
+    receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("Timed out");
+}
+
+#[test]
+fn flush_with_thread_consumers() {
+    within_timeout(|| {
+        let dropper: Dropper<Counted> = DropperBuilder::new()
+            .workers(3)
+            .build_with(ThreadSpawner::new())
+            .unwrap();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        for _ in 0..100 {
+            dropper.dropout(Counted(Arc::clone(&dropped)));
+        }
+        dropper.flush();
+        assert_eq!(dropped.load(Ordering::SeqCst), 100);
+        dropper.shutdown().unwrap();
+    });
+}
+
+#[test]
+fn drop_joins_thread_consumers() {
+    within_timeout(|| {
+        let dropper: Dropper<(Counted, Sleep)> = DropperBuilder::new()
+            .workers(2)
+            .build_with(ThreadSpawner::new())
+            .unwrap();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        for _ in 0..20 {
+            dropper.dropout((Counted(Arc::clone(&dropped)), Sleep));
+        }
+        drop(dropper);
+        assert_eq!(dropped.load(Ordering::SeqCst), 20);
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn flush_with_tasks_on_a_current_thread_runtime() {
+    within_timeout(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let dropper: Dropper<Counted> = DropperBuilder::new()
+                .workers(2)
+                .build_with(dropout::TokioSpawner::current().unwrap())
+                .unwrap();
+            let dropped = Arc::new(AtomicUsize::new(0));
+            for _ in 0..100 {
+                dropper.dropout(Counted(Arc::clone(&dropped)));
+            }
+            dropper.flush_async().await;
+            assert_eq!(dropped.load(Ordering::SeqCst), 100);
+            dropper.flush_async().await;
+        });
+    });
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn flush_with_tasks_on_a_thread_pool() {
+    within_timeout(|| {
+        let pool = futures::executor::ThreadPool::builder()
+            .pool_size(1)
+            .create()
+            .unwrap();
+        let dropper: Dropper<Counted> = DropperBuilder::new()
+            .workers(3)
+            .build_with(dropout::FuturesSpawner(pool))
+            .unwrap();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        for _ in 0..100 {
+            dropper.dropout(Counted(Arc::clone(&dropped)));
+        }
+        futures::executor::block_on(dropper.flush_async());
+        assert_eq!(dropped.load(Ordering::SeqCst), 100);
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn tokio_channel_with_concurrent_producers() {
+    within_timeout(|| {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let builder = DropperBuilder::new().capacity(2).tokio_channel();
+            let dropper = Arc::new(dropout::AsyncDropper::with_builder(builder).unwrap());
+            let dropped = Arc::new(AtomicUsize::new(0));
+            let producers: Vec<_> = (0..4)
+                .map(|_| {
+                    let dropper = Arc::clone(&dropper);
+                    let dropped = Arc::clone(&dropped);
+                    tokio::spawn(async move {
+                        for _ in 0..100 {
+                            dropper.dropout(Counted(Arc::clone(&dropped))).await;
+                        }
+                    })
+                })
+                .collect();
+            for producer in producers {
+                producer.await.unwrap();
+            }
+            dropper.flush().await;
+            assert_eq!(dropped.load(Ordering::SeqCst), 400);
+            assert_eq!(dropper.pending(), 0);
+        });
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn tokio_channel_full_without_a_running_consumer() {
+    within_timeout(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let builder = DropperBuilder::new().capacity(1).tokio_channel();
+        let dropper = {
+            let _runtime = runtime.enter();
+            dropout::AsyncDropper::with_builder(builder).unwrap()
+        };
+        let dropped = Arc::new(AtomicUsize::new(0));
+        // The runtime is not running: the consumer doesn't receive the values.
+        assert!(dropper.try_dropout(Counted(Arc::clone(&dropped))).is_ok());
+        assert!(dropper.try_dropout(Counted(Arc::clone(&dropped))).is_err());
+        assert!(dropper
+            .as_dropper()
+            .dropout_timeout(Counted(Arc::clone(&dropped)), Duration::from_millis(10))
+            .is_err());
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+        runtime.block_on(dropper.flush());
+        assert_eq!(dropped.load(Ordering::SeqCst), 3);
+        assert_eq!(dropper.pending(), 0);
+    });
+}
+
+#[test]
+fn yield_between_chunks_with_tasks() {
+    let (sender, consumers) = mpsc::channel();
+    let dropper: Dropper<Counted> = DropperBuilder::new()
+        .task_budget(1)
+        .build_with(move |consumer| {
+            sender.send(consumer).unwrap();
+            Ok(())
+        })
+        .unwrap();
+    let mut consumer = consumers.try_recv().unwrap();
+    let mut poll = || {
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(Pin::new(&mut consumer).poll(&mut cx), Poll::Pending);
+    };
+    let chunked = Arc::new(AtomicUsize::new(0));
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let collection: Vec<_> = (0..3).map(|_| Counted(Arc::clone(&chunked))).collect();
+    dropper.dropout_chunked(collection, 1);
+    poll();
+    poll();
+    assert_eq!(chunked.load(Ordering::SeqCst), 1);
+    // The values queued after the collection are dropped between two chunks.
+    dropper.dropout(Counted(Arc::clone(&dropped)));
+    poll();
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    assert_eq!(chunked.load(Ordering::SeqCst), 1);
+    poll();
+    poll();
+    assert_eq!(chunked.load(Ordering::SeqCst), 3);
+    // The collection is released once its last chunk comes up empty.
+    poll();
+    assert_eq!(dropper.pending(), 0);
+}
+
+/// Pending until opened.
+#[derive(Clone, Default)]
+struct Gate(Arc<Mutex<(bool, Option<Waker>)>>);
+
+impl Gate {
+    fn open(&self) {
+        let mut gate = self.0.lock().unwrap();
+        gate.0 = true;
+        if let Some(waker) = gate.1.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for Gate {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut gate = self.0.lock().unwrap();
+        match gate.0 {
+            true => Poll::Ready(()),
+            false => {
+                gate.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[test]
+fn poll_cleanups_with_tasks() {
+    let (sender, consumers) = mpsc::channel();
+    let dropper: Dropper<Counted> = DropperBuilder::new()
+        .build_with(move |consumer| {
+            sender.send(consumer).unwrap();
+            Ok(())
+        })
+        .unwrap();
+    let mut consumer = consumers.try_recv().unwrap();
+    let mut poll = || {
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(Pin::new(&mut consumer).poll(&mut cx), Poll::Pending);
+    };
+    let gate = Gate::default();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    dropper.defer_async(gate.clone());
+    dropper.dropout(Counted(Arc::clone(&dropped)));
+    poll();
+    // The consumer doesn't wait for the future to complete.
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    assert_eq!(dropper.pending(), 1);
+    gate.open();
+    poll();
+    assert_eq!(dropper.pending(), 0);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn defer_async_with_a_tokio_timer() {
+    within_timeout(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let dropper = dropout::AsyncDropper::<Counted>::new();
+            let dropped = Arc::new(AtomicUsize::new(0));
+            let counted = Counted(Arc::clone(&dropped));
+            dropper.as_dropper().defer_async(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                drop(counted);
+            });
+            dropper.flush().await;
+            assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        });
+    });
+}
@@ -0,0 +1,52 @@
+use dropout::{Dropper, DropperBuilder, DropperPool};
+use std::any;
+use std::collections::BTreeSet;
+
+/// The type names of the drops tracked by `track_slowest`.
+fn type_names(stats: dropout::DropperStats) -> BTreeSet<&'static str> {
+    stats.slowest.iter().map(|drop| drop.type_name).collect()
+}
+
+#[test]
+fn type_names_of_typed_values() {
+    let dropper: Dropper<Vec<u8>> = DropperBuilder::new().track_slowest(8).build().unwrap();
+    dropper.dropout(vec![0; 1024]);
+    dropper.dropout_boxed(vec![0; 1024]);
+    dropper.dropout_many(vec![vec![0; 1024]; 4]);
+    dropper.flush();
+    assert_eq!(
+        type_names(dropper.stats()),
+        BTreeSet::from([any::type_name::<Vec<u8>>()])
+    );
+}
+
+#[test]
+fn type_names_of_erased_values() {
+    let pool = DropperPool::with_builder(DropperBuilder::new().track_slowest(8), 1).unwrap();
+    pool.dropout(String::from("value"));
+    pool.try_dropout(vec![0u64; 16]).unwrap();
+    let dropper = Dropper::<Vec<u8>>::spawn_on(&pool);
+    dropper.dropout(vec![0; 1024]);
+    dropper.dropout_many(vec![vec![0; 1024]; 4]);
+    pool.flush();
+    assert_eq!(
+        type_names(pool.stats()),
+        BTreeSet::from([
+            any::type_name::<String>(),
+            any::type_name::<Vec<u64>>(),
+            any::type_name::<Vec<u8>>(),
+        ])
+    );
+}
+
+#[test]
+fn type_names_of_closures() {
+    let pool = DropperPool::with_builder(DropperBuilder::new().track_slowest(8), 1).unwrap();
+    pool.defer(|| {});
+    pool.flush();
+    let stats = pool.stats();
+    assert_eq!(stats.slowest.len(), 1);
+    assert!(stats.slowest[0]
+        .type_name
+        .contains("type_names_of_closures"));
+}
@@ -0,0 +1,54 @@
+use dropout::Dropper;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Reports when it is dropped.
+struct Timed(Sender<Instant>);
+
+impl Drop for Timed {
+    fn drop(&mut self) {
+        let _ = self.0.send(Instant::now());
+    }
+}
+
+#[test]
+fn touch_postpones_the_expiry() {
+    let dropper = Dropper::new();
+    // Keep the expired value in the queue.
+    dropper.pause();
+    let (sender, dropped) = mpsc::channel();
+    let ttl = Duration::from_millis(50);
+    let entry = dropper.expire_after(Timed(sender), ttl);
+    thread::sleep(ttl / 2);
+    let touched = Instant::now();
+    entry.touch(ttl);
+    while !entry.is_expired() {
+        assert!(touched.elapsed() < Duration::from_secs(5), "Not expired");
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(Instant::now() >= touched + ttl);
+    // Sent to the dropper, it can no longer be touched or cancelled.
+    entry.touch(ttl);
+    assert!(entry.cancel().is_none());
+    assert_eq!(dropper.pending(), 1);
+    assert!(dropped.try_recv().is_err());
+    dropper.resume();
+    dropper.flush();
+    assert!(dropped.try_recv().is_ok());
+}
+
+#[test]
+fn cancel_before_the_expiry() {
+    let dropper = Dropper::new();
+    let (sender, dropped) = mpsc::channel();
+    let entry = dropper.expire_after(Timed(sender), Duration::from_millis(10));
+    let value = entry.cancel().unwrap();
+    assert!(entry.is_expired());
+    thread::sleep(Duration::from_millis(50));
+    dropper.flush();
+    assert_eq!(dropper.pending(), 0);
+    assert!(dropped.try_recv().is_err());
+    drop(value);
+    assert!(dropped.try_recv().is_ok());
+}
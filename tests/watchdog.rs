@@ -0,0 +1,34 @@
+use dropout::{Dropper, DropperBuilder};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Blocks the dropper thread in its drop, until released.
+struct Blocker(Receiver<()>);
+
+impl Drop for Blocker {
+    fn drop(&mut self) {
+        let _ = self.0.recv();
+    }
+}
+
+#[test]
+fn report_a_stuck_drop_once() {
+    let (stuck_sender, stuck) = mpsc::channel();
+    let dropper: Dropper<Blocker> = DropperBuilder::new()
+        .watchdog(Duration::from_millis(20), move |stuck| {
+            let _ = stuck_sender.send((stuck.type_name, stuck.count, stuck.elapsed));
+        })
+        .build()
+        .unwrap();
+    let (release, blocked) = mpsc::channel();
+    dropper.dropout(Blocker(blocked));
+    let (type_name, count, elapsed) = stuck.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(type_name.ends_with("Blocker"));
+    assert_eq!(count, 1);
+    assert!(elapsed >= Duration::from_millis(20));
+    // Each stuck drop is reported once.
+    assert!(stuck.recv_timeout(Duration::from_millis(100)).is_err());
+    release.send(()).unwrap();
+    dropper.flush();
+    assert!(stuck.try_recv().is_err());
+}